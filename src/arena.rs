@@ -0,0 +1,322 @@
+//! Procedural interior arena layouts.
+//!
+//! The original match was an empty rectangle bounded only by the screen
+//! border. `Arena` adds interior wall segments generated from the match's
+//! `GameRng`, so layouts are reproducible from the same seed as everything
+//! else. Walls are pushed into the shared `CollisionGrid` as a dense run of
+//! points under a dedicated owner index, exactly the way a cycle pushes its
+//! trail, so `LightCycle::update` and the AI's look-ahead need no special
+//! casing to treat them as solid.
+
+use crate::collision::CollisionGrid;
+use crate::{GameRng, CELL_SIZE, GRID_HEIGHT, GRID_WIDTH};
+use ggez::graphics::Rect;
+use ggez::mint::Point2;
+use rand::Rng;
+use std::collections::HashSet;
+
+/// Spacing between points sampled along a wall segment before they're
+/// pushed into the `CollisionGrid`, fine enough that the per-point collision
+/// radius (`CELL_SIZE`) leaves no gaps a cycle could slip through.
+const WALL_POINT_SPACING: f32 = 6.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArenaLayout {
+    Empty,
+    SymmetricPillars,
+    Maze,
+    Caves,
+}
+
+impl ArenaLayout {
+    /// Cycle to the next layout, for the menu's "press L to change" control.
+    pub fn next(self) -> Self {
+        match self {
+            ArenaLayout::Empty => ArenaLayout::SymmetricPillars,
+            ArenaLayout::SymmetricPillars => ArenaLayout::Maze,
+            ArenaLayout::Maze => ArenaLayout::Caves,
+            ArenaLayout::Caves => ArenaLayout::Empty,
+        }
+    }
+}
+
+/// A procedurally generated set of interior walls. `segments` (line-shaped
+/// walls: pillar outlines, maze corridors) and `blocks` (filled cells from
+/// `ArenaLayout::Caves`'s cellular automata) are both stored in world space
+/// so they can be drawn directly and pushed into a `CollisionGrid`.
+pub struct Arena {
+    pub segments: Vec<(Point2<f32>, Point2<f32>)>,
+    pub blocks: Vec<Rect>,
+}
+
+impl Arena {
+    /// Build a layout from `rng`, sized to the play area. Call with the same
+    /// `GameRng` a match was seeded with so a seed fully reproduces it.
+    /// `spawn_a`/`spawn_b` are the two players' start positions, which
+    /// layouts that carve obstacles (e.g. `Caves`) must leave clear.
+    pub fn generate(
+        layout: ArenaLayout,
+        rng: &mut GameRng,
+        spawn_a: Point2<f32>,
+        spawn_b: Point2<f32>,
+    ) -> Self {
+        match layout {
+            ArenaLayout::Empty => Arena { segments: Vec::new(), blocks: Vec::new() },
+            ArenaLayout::SymmetricPillars => Arena { segments: symmetric_pillars(rng), blocks: Vec::new() },
+            ArenaLayout::Maze => Arena { segments: maze(rng), blocks: Vec::new() },
+            ArenaLayout::Caves => Arena { segments: Vec::new(), blocks: caves(rng, spawn_a, spawn_b) },
+        }
+    }
+
+    /// Push every wall as a dense run of points into `grid` under `owner`.
+    /// `owner` must be an index `CollisionGrid` was sized for that no
+    /// `LightCycle` uses, so the self-collision grace period never exempts
+    /// a wall point.
+    pub fn populate_grid(&self, grid: &mut CollisionGrid, owner: usize) {
+        for &(start, end) in &self.segments {
+            let dx = end.x - start.x;
+            let dy = end.y - start.y;
+            let length = (dx * dx + dy * dy).sqrt();
+            let steps = (length / WALL_POINT_SPACING).ceil().max(1.0) as usize;
+            for i in 0..=steps {
+                let t = i as f32 / steps as f32;
+                grid.push_point(
+                    owner,
+                    Point2 {
+                        x: start.x + dx * t,
+                        y: start.y + dy * t,
+                    },
+                );
+            }
+        }
+
+        // One center point per block is enough: adjacent `CELL_SIZE` cells
+        // tile without gaps and the collision check's radius is `CELL_SIZE`.
+        for block in &self.blocks {
+            grid.push_point(
+                owner,
+                Point2 {
+                    x: block.x + block.w / 2.0,
+                    y: block.y + block.h / 2.0,
+                },
+            );
+        }
+    }
+}
+
+/// Axis-aligned square pillar outline, as four line segments.
+fn pillar_segments(x: f32, y: f32, size: f32) -> [(Point2<f32>, Point2<f32>); 4] {
+    let tl = Point2 { x, y };
+    let tr = Point2 { x: x + size, y };
+    let bl = Point2 { x, y: y + size };
+    let br = Point2 { x: x + size, y: y + size };
+    [(tl, tr), (tr, br), (br, bl), (bl, tl)]
+}
+
+/// 2-4 square pillars placed in the left half and mirrored into the right
+/// half, keeping the layout fair for both players.
+fn symmetric_pillars(rng: &mut GameRng) -> Vec<(Point2<f32>, Point2<f32>)> {
+    const MARGIN: f32 = 120.0;
+    let pillar_count = rng.gen_range(2..=4);
+    let mut segments = Vec::new();
+
+    for _ in 0..pillar_count {
+        let size = rng.gen_range(60.0..=120.0);
+        let x = rng.gen_range(MARGIN * 2.0..(GRID_WIDTH / 2.0 - MARGIN - size));
+        let y = rng.gen_range(MARGIN..(GRID_HEIGHT - MARGIN - size));
+
+        segments.extend(pillar_segments(x, y, size));
+        segments.extend(pillar_segments(GRID_WIDTH - x - size, y, size));
+    }
+
+    segments
+}
+
+const MAZE_COLS: i32 = 6;
+const MAZE_ROWS: i32 = 4;
+const MAZE_CELL: f32 = 150.0;
+
+/// A corridor maze carved with a randomized depth-first backtracker over a
+/// `MAZE_COLS` x `MAZE_ROWS` grid of cells centered in the arena; walls
+/// between cells survive unless the carve visits both sides.
+fn maze(rng: &mut GameRng) -> Vec<(Point2<f32>, Point2<f32>)> {
+    let origin_x = (GRID_WIDTH - MAZE_COLS as f32 * MAZE_CELL) / 2.0;
+    let origin_y = (GRID_HEIGHT - MAZE_ROWS as f32 * MAZE_CELL) / 2.0;
+
+    // Edges still standing, keyed by the pair of cells they separate.
+    let mut walls: HashSet<((i32, i32), (i32, i32))> = HashSet::new();
+    for col in 0..MAZE_COLS {
+        for row in 0..MAZE_ROWS {
+            if col + 1 < MAZE_COLS {
+                walls.insert(((col, row), (col + 1, row)));
+            }
+            if row + 1 < MAZE_ROWS {
+                walls.insert(((col, row), (col, row + 1)));
+            }
+        }
+    }
+
+    let mut visited = vec![vec![false; MAZE_ROWS as usize]; MAZE_COLS as usize];
+    let mut stack = vec![(rng.gen_range(0..MAZE_COLS), rng.gen_range(0..MAZE_ROWS))];
+    visited[stack[0].0 as usize][stack[0].1 as usize] = true;
+
+    while let Some(&(col, row)) = stack.last() {
+        let mut neighbors = Vec::new();
+        for (ncol, nrow) in [(col - 1, row), (col + 1, row), (col, row - 1), (col, row + 1)] {
+            if ncol >= 0 && ncol < MAZE_COLS && nrow >= 0 && nrow < MAZE_ROWS && !visited[ncol as usize][nrow as usize] {
+                neighbors.push((ncol, nrow));
+            }
+        }
+
+        if neighbors.is_empty() {
+            stack.pop();
+            continue;
+        }
+
+        let next = neighbors[rng.gen_range(0..neighbors.len())];
+        let edge = if (col, row) < next { ((col, row), next) } else { (next, (col, row)) };
+        walls.remove(&edge);
+        visited[next.0 as usize][next.1 as usize] = true;
+        stack.push(next);
+    }
+
+    let mut segments: Vec<(Point2<f32>, Point2<f32>)> = walls
+        .into_iter()
+        .map(|(a, b)| {
+            let ax = origin_x + a.0 as f32 * MAZE_CELL;
+            let ay = origin_y + a.1 as f32 * MAZE_CELL;
+            if a.0 == b.0 {
+                // Same column, adjacent rows: the wall between them runs horizontally.
+                (
+                    Point2 { x: ax, y: ay + MAZE_CELL },
+                    Point2 { x: ax + MAZE_CELL, y: ay + MAZE_CELL },
+                )
+            } else {
+                // Same row, adjacent columns: the wall between them runs vertically.
+                (
+                    Point2 { x: ax + MAZE_CELL, y: ay },
+                    Point2 { x: ax + MAZE_CELL, y: ay + MAZE_CELL },
+                )
+            }
+        })
+        .collect();
+
+    // The carved grid only spans the arena's center; without these, the open
+    // strips above and below it (and both spawns sit inside the carved
+    // rows) let a cycle just drive around the entire maze instead of
+    // through it. Seal those strips across the full arena width.
+    let top = origin_y;
+    let bottom = origin_y + MAZE_ROWS as f32 * MAZE_CELL;
+    segments.push((Point2 { x: 0.0, y: top }, Point2 { x: GRID_WIDTH, y: top }));
+    segments.push((Point2 { x: 0.0, y: bottom }, Point2 { x: GRID_WIDTH, y: bottom }));
+
+    segments
+}
+
+/// Probability a cave cell starts out as a wall, before smoothing.
+const CAVE_FILL_PROB: f64 = 0.45;
+/// Smoothing passes run over the noise grid; enough for it to coalesce into
+/// connected caverns instead of staying speckled.
+const CAVE_SMOOTH_ITERATIONS: usize = 5;
+/// Radius force-cleared around each spawn point.
+const CAVE_SPAWN_CLEAR_RADIUS: f32 = 120.0;
+/// Half-height of the guaranteed-clear corridor running between the two
+/// spawn points.
+const CAVE_CORRIDOR_HALF_HEIGHT: f32 = 48.0;
+
+/// Cave-like obstacles carved with the standard cellular-automata algorithm:
+/// seed a `GRID_WIDTH/CELL_SIZE` x `GRID_HEIGHT/CELL_SIZE` grid with noise,
+/// then repeatedly let each cell become a wall if it has a wall majority in
+/// its Moore neighborhood (cells outside the grid count as walls) or open
+/// up if it's a clear minority. A radius around each spawn and a corridor
+/// between them are force-cleared afterwards so neither player ever starts
+/// boxed in.
+fn caves(rng: &mut GameRng, spawn_a: Point2<f32>, spawn_b: Point2<f32>) -> Vec<Rect> {
+    let cols = (GRID_WIDTH / CELL_SIZE) as usize;
+    let rows = (GRID_HEIGHT / CELL_SIZE) as usize;
+
+    let mut grid = vec![vec![false; rows]; cols];
+    for row in grid.iter_mut() {
+        for cell in row.iter_mut() {
+            *cell = rng.gen_bool(CAVE_FILL_PROB);
+        }
+    }
+
+    for _ in 0..CAVE_SMOOTH_ITERATIONS {
+        let mut next = vec![vec![false; rows]; cols];
+        for col in 0..cols {
+            for row in 0..rows {
+                let wall_neighbors = cave_moore_wall_count(&grid, col as i32, row as i32, cols, rows);
+                next[col][row] = if wall_neighbors >= 5 {
+                    true
+                } else if wall_neighbors <= 3 {
+                    false
+                } else {
+                    grid[col][row]
+                };
+            }
+        }
+        grid = next;
+    }
+
+    for col in 0..cols {
+        for row in 0..rows {
+            let center = Point2 {
+                x: col as f32 * CELL_SIZE + CELL_SIZE / 2.0,
+                y: row as f32 * CELL_SIZE + CELL_SIZE / 2.0,
+            };
+            if cave_dist(center, spawn_a) < CAVE_SPAWN_CLEAR_RADIUS
+                || cave_dist(center, spawn_b) < CAVE_SPAWN_CLEAR_RADIUS
+                || cave_in_corridor(center, spawn_a, spawn_b)
+            {
+                grid[col][row] = false;
+            }
+        }
+    }
+
+    let mut blocks = Vec::new();
+    for col in 0..cols {
+        for row in 0..rows {
+            if grid[col][row] {
+                blocks.push(Rect::new(col as f32 * CELL_SIZE, row as f32 * CELL_SIZE, CELL_SIZE, CELL_SIZE));
+            }
+        }
+    }
+    blocks
+}
+
+/// Count walls in the 8-cell Moore neighborhood of `(col, row)`; cells
+/// outside `[0, cols) x [0, rows)` count as walls.
+fn cave_moore_wall_count(grid: &[Vec<bool>], col: i32, row: i32, cols: usize, rows: usize) -> u32 {
+    let mut count = 0;
+    for dc in -1..=1 {
+        for dr in -1..=1 {
+            if dc == 0 && dr == 0 {
+                continue;
+            }
+            let (nc, nr) = (col + dc, row + dr);
+            let is_wall = if nc < 0 || nr < 0 || nc >= cols as i32 || nr >= rows as i32 {
+                true
+            } else {
+                grid[nc as usize][nr as usize]
+            };
+            if is_wall {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+fn cave_dist(a: Point2<f32>, b: Point2<f32>) -> f32 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
+
+/// Whether `point` falls inside the clear strip running between the two
+/// spawn points.
+fn cave_in_corridor(point: Point2<f32>, spawn_a: Point2<f32>, spawn_b: Point2<f32>) -> bool {
+    let min_x = spawn_a.x.min(spawn_b.x);
+    let max_x = spawn_a.x.max(spawn_b.x);
+    let mid_y = (spawn_a.y + spawn_b.y) / 2.0;
+    point.x >= min_x && point.x <= max_x && (point.y - mid_y).abs() < CAVE_CORRIDOR_HALF_HEIGHT
+}
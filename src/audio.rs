@@ -0,0 +1,261 @@
+//! Procedurally synthesized sound effects — no sample files ship with the
+//! game. Each cycle's engine hum and every triggered one-shot (boost
+//! whoosh, explosion) is built from oscillator/envelope math directly into
+//! an `f32` PCM buffer, mixed down by summing and clamping, and handed to
+//! ggez's audio backend a few ticks' worth at a time.
+
+use crate::GameRng;
+use ggez::audio::{SoundData, SoundSource, Source};
+use ggez::{Context, GameResult};
+use rand::Rng;
+
+/// Sample rate for every synthesized buffer.
+const SAMPLE_RATE: u32 = 22_050;
+/// One call to `mix_frame` renders this many seconds of audio, matching the
+/// fixed 60Hz tick so hums and one-shots stay in lockstep with gameplay.
+const FRAME_DURATION: f32 = 1.0 / 60.0;
+
+const HUM_BASE_HZ: f32 = 70.0;
+const HUM_BOOST_HZ: f32 = 200.0;
+const HUM_GAIN: f32 = 0.12;
+
+const WHOOSH_DURATION: f32 = 0.25;
+const WHOOSH_START_HZ: f32 = 250.0;
+const WHOOSH_END_HZ: f32 = 900.0;
+const WHOOSH_GAIN: f32 = 0.5;
+
+const EXPLOSION_DURATION: f32 = 0.5;
+const EXPLOSION_DECAY_RATE: f32 = 7.0;
+const EXPLOSION_GAIN: f32 = 0.8;
+
+/// Ticks rendered into a single clip before it's dispatched to ggez.
+/// `SAMPLE_RATE / 60` isn't a whole number of samples, so dispatching one
+/// clip per tick means every clip's length has to be rounded and `play_detached`
+/// gives no guarantee those clips schedule back-to-back with sample-accurate
+/// timing — the rounding error and any scheduling gap both compound over a
+/// match into audible clicks or drift. Batching ticks into fewer, longer
+/// clips cuts down how often that scheduling boundary is hit; `sample_debt`
+/// below keeps the rounding itself from accumulating at all.
+const BATCH_TICKS: usize = 4;
+
+/// Sawtooth wave in [-1, 1], `phase` in cycles (not radians).
+fn sawtooth(phase: f32) -> f32 {
+    2.0 * (phase - (phase + 0.5).floor())
+}
+
+/// Square wave in [-1, 1], `phase` in cycles.
+fn square(phase: f32) -> f32 {
+    if phase.fract().abs() < 0.5 { 1.0 } else { -1.0 }
+}
+
+/// A cycle's continuous engine tone: a sawtooth whose frequency tracks its
+/// current speed/boost state, retuned every frame rather than restarted.
+struct EngineHum {
+    active: bool,
+    phase: f32,
+    frequency: f32,
+}
+
+/// A one-shot effect mid-playback, tracked by how far into its own
+/// envelope it is so `mix_frame` can render just this frame's slice.
+enum OneShot {
+    BoostWhoosh { elapsed: f32 },
+    Explosion { elapsed: f32 },
+}
+
+impl OneShot {
+    fn duration(&self) -> f32 {
+        match self {
+            OneShot::BoostWhoosh { .. } => WHOOSH_DURATION,
+            OneShot::Explosion { .. } => EXPLOSION_DURATION,
+        }
+    }
+
+    fn elapsed(&self) -> f32 {
+        match self {
+            OneShot::BoostWhoosh { elapsed } | OneShot::Explosion { elapsed } => *elapsed,
+        }
+    }
+
+    fn advance(&mut self, dt: f32) {
+        match self {
+            OneShot::BoostWhoosh { elapsed } | OneShot::Explosion { elapsed } => *elapsed += dt,
+        }
+    }
+
+    /// This voice's contribution to the sample `t` seconds into its life.
+    fn sample(&self, t: f32, rng: &mut GameRng) -> f32 {
+        match self {
+            OneShot::BoostWhoosh { .. } => {
+                let progress = (t / WHOOSH_DURATION).clamp(0.0, 1.0);
+                let frequency = WHOOSH_START_HZ + (WHOOSH_END_HZ - WHOOSH_START_HZ) * progress;
+                square(t * frequency) * (1.0 - progress) * WHOOSH_GAIN
+            }
+            OneShot::Explosion { .. } => {
+                let noise = rng.gen_range(-1.0..=1.0);
+                noise * (-t * EXPLOSION_DECAY_RATE).exp() * EXPLOSION_GAIN
+            }
+        }
+    }
+}
+
+/// Wraps a mono `f32` PCM buffer as a minimal 16-bit WAV file so ggez's
+/// decoder (which expects an encoded format, not a raw float array) can
+/// load it straight from memory.
+fn wav_bytes(samples: &[f32]) -> Vec<u8> {
+    let mut pcm = Vec::with_capacity(samples.len() * 2);
+    for &sample in samples {
+        pcm.extend_from_slice(&((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_le_bytes());
+    }
+
+    let data_len = pcm.len() as u32;
+    let byte_rate = SAMPLE_RATE * 2;
+    let mut wav = Vec::with_capacity(44 + pcm.len());
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVE");
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&byte_rate.to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes()); // block align
+    wav.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    wav.extend_from_slice(&pcm);
+    wav
+}
+
+/// Owns every active voice (per-cycle engine hums plus one-shots) and mixes
+/// them down into one clip per frame.
+pub struct AudioEngine {
+    hums: Vec<EngineHum>,
+    one_shots: Vec<OneShot>,
+    /// Samples rendered for the clip currently being assembled; flushed to
+    /// ggez once it covers `BATCH_TICKS` ticks of game time.
+    pending: Vec<f32>,
+    /// How many ticks' worth of samples are in `pending` so far.
+    pending_ticks: usize,
+    /// Fractional sample left over from the last tick's rounding, carried
+    /// into the next tick so the average samples-per-tick across a match
+    /// stays exactly `SAMPLE_RATE * FRAME_DURATION` instead of drifting
+    /// from always rounding the same direction.
+    sample_debt: f32,
+    /// Set by `trigger_explosion`: a match can end on the very tick an
+    /// explosion is queued, and the state stack tears down without calling
+    /// `mix_frame` again, so waiting for a full `BATCH_TICKS` batch would
+    /// silently drop the cue. Forces the next `mix_frame` to flush
+    /// `pending` immediately, partial batch or not.
+    force_flush: bool,
+}
+
+impl AudioEngine {
+    pub fn new() -> Self {
+        AudioEngine {
+            hums: Vec::new(),
+            one_shots: Vec::new(),
+            pending: Vec::new(),
+            pending_ticks: 0,
+            sample_debt: 0.0,
+            force_flush: false,
+        }
+    }
+
+    /// (Re)allocate one hum slot per cycle for a fresh match. Any audio
+    /// still waiting to be flushed from the previous match is dropped
+    /// rather than played late; it's at most `BATCH_TICKS - 1` ticks, too
+    /// short to notice.
+    pub fn reset(&mut self, cycle_count: usize) {
+        self.hums.clear();
+        self.hums.resize_with(cycle_count, || EngineHum {
+            active: false,
+            phase: 0.0,
+            frequency: HUM_BASE_HZ,
+        });
+        self.one_shots.clear();
+        self.pending.clear();
+        self.pending_ticks = 0;
+        self.sample_debt = 0.0;
+        self.force_flush = false;
+    }
+
+    /// Retune cycle `index`'s hum toward its current speed; silent while
+    /// `alive` is false.
+    pub fn set_hum(&mut self, index: usize, alive: bool, boosting: bool) {
+        let Some(hum) = self.hums.get_mut(index) else {
+            return;
+        };
+        hum.active = alive;
+        hum.frequency = if boosting { HUM_BOOST_HZ } else { HUM_BASE_HZ };
+    }
+
+    /// Queue a boost-start whoosh to be mixed in on the next `mix_frame`.
+    pub fn trigger_boost_start(&mut self) {
+        self.one_shots.push(OneShot::BoostWhoosh { elapsed: 0.0 });
+    }
+
+    /// Queue an explosion noise burst to be mixed in on the next `mix_frame`.
+    /// This is the match-ending cue, so it also forces an immediate flush —
+    /// see `force_flush`.
+    pub fn trigger_explosion(&mut self) {
+        self.one_shots.push(OneShot::Explosion { elapsed: 0.0 });
+        self.force_flush = true;
+    }
+
+    /// Render this tick's slice of every active voice and append it to the
+    /// clip being assembled. Once `BATCH_TICKS` ticks have accumulated (or
+    /// `force_flush` demands it sooner), the whole clip is handed to ggez
+    /// as a single detached one-shot, instead of dispatching (and
+    /// re-encoding/re-decoding) a brand-new clip every tick. A fully silent
+    /// stretch (no active hums, no one-shots, and nothing already pending)
+    /// plays nothing.
+    pub fn mix_frame(&mut self, ctx: &mut Context, rng: &mut GameRng) -> GameResult {
+        if self.pending.is_empty() && self.hums.iter().all(|h| !h.active) && self.one_shots.is_empty() {
+            return Ok(());
+        }
+
+        self.sample_debt += SAMPLE_RATE as f32 * FRAME_DURATION;
+        let sample_count = self.sample_debt.floor() as usize;
+        self.sample_debt -= sample_count as f32;
+        let tick_duration = sample_count as f32 / SAMPLE_RATE as f32;
+
+        let mut buffer = vec![0.0f32; sample_count];
+
+        for hum in &mut self.hums {
+            if !hum.active {
+                continue;
+            }
+            for (i, sample) in buffer.iter_mut().enumerate() {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                *sample += sawtooth(hum.phase + t * hum.frequency) * HUM_GAIN;
+            }
+            hum.phase = (hum.phase + tick_duration * hum.frequency).fract();
+        }
+
+        for voice in &self.one_shots {
+            for (i, sample) in buffer.iter_mut().enumerate() {
+                let t = voice.elapsed() + i as f32 / SAMPLE_RATE as f32;
+                *sample += voice.sample(t, rng);
+            }
+        }
+        for voice in &mut self.one_shots {
+            voice.advance(tick_duration);
+        }
+        self.one_shots.retain(|voice| voice.elapsed() < voice.duration());
+
+        self.pending.extend_from_slice(&buffer);
+        self.pending_ticks += 1;
+        if self.pending_ticks < BATCH_TICKS && !self.force_flush {
+            return Ok(());
+        }
+
+        let data = SoundData::from_bytes(&wav_bytes(&self.pending));
+        Source::from_data(ctx, data)?.play_detached(ctx)?;
+        self.pending.clear();
+        self.pending_ticks = 0;
+        self.force_flush = false;
+        Ok(())
+    }
+}
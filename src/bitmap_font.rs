@@ -0,0 +1,88 @@
+//! Batched bitmap-font text rendering, replacing ggez's default
+//! `graphics::Text` so HUD/menu text matches the pixel-art cycles and
+//! trails instead of an anti-aliased system font.
+//!
+//! Glyphs come from a monospaced ASCII atlas image (`BITMAP_FONT_PATH`):
+//! fixed-size cells packed `GLYPHS_PER_ROW` wide, starting at the space
+//! character (0x20). `draw_text` looks up each byte's cell and batches
+//! every glyph quad into a single `InstanceArray`, so a whole string costs
+//! one draw call instead of one mesh per label.
+
+use ggez::graphics::{Canvas, Color, DrawParam, Image, InstanceArray, Rect};
+use ggez::Context;
+
+/// Default location for the font atlas, relative to ggez's resource dir
+/// (matching `EMITTER_CONFIG_PATH`'s convention in `particles.rs`).
+pub const BITMAP_FONT_PATH: &str = "/bitmap_font.png";
+
+/// First and last ASCII byte the atlas has a glyph for; anything outside
+/// this range is skipped.
+const FIRST_GLYPH: u8 = b' ';
+const LAST_GLYPH: u8 = b'~';
+const GLYPHS_PER_ROW: u32 = 16;
+
+/// A loaded glyph atlas: the source image plus the fixed cell size used to
+/// slice it into per-character source rects.
+pub struct BitmapFont {
+    atlas: Image,
+    cell_width: f32,
+    cell_height: f32,
+    /// Extra space, in atlas pixels, inserted after each glyph when a
+    /// string is laid out.
+    advance_padding: f32,
+}
+
+impl BitmapFont {
+    /// Load the glyph atlas from `path`. Returns `None` (rather than a
+    /// placeholder atlas) if the file is missing, so callers can fall back
+    /// to `graphics::Text` instead of drawing nothing.
+    pub fn load(ctx: &mut Context, path: &str) -> Option<Self> {
+        let atlas = Image::from_path(ctx, path).ok()?;
+        let glyph_count = (LAST_GLYPH - FIRST_GLYPH) as u32 + 1;
+        let rows = (glyph_count + GLYPHS_PER_ROW - 1) / GLYPHS_PER_ROW;
+        Some(BitmapFont {
+            cell_width: atlas.width() as f32 / GLYPHS_PER_ROW as f32,
+            cell_height: atlas.height() as f32 / rows.max(1) as f32,
+            advance_padding: 1.0,
+            atlas,
+        })
+    }
+
+    /// This glyph's source rect within the atlas, in normalized `[0, 1]`
+    /// UV coordinates as `DrawParam::src` expects.
+    fn glyph_uv(&self, byte: u8) -> Option<Rect> {
+        if !(FIRST_GLYPH..=LAST_GLYPH).contains(&byte) {
+            return None;
+        }
+        let index = (byte - FIRST_GLYPH) as u32;
+        let col = index % GLYPHS_PER_ROW;
+        let row = index / GLYPHS_PER_ROW;
+        Some(Rect::new(
+            col as f32 * self.cell_width / self.atlas.width() as f32,
+            row as f32 * self.cell_height / self.atlas.height() as f32,
+            self.cell_width / self.atlas.width() as f32,
+            self.cell_height / self.atlas.height() as f32,
+        ))
+    }
+
+    /// Draw `text` at `pos`, scaled by `scale` and tinted `color`, batching
+    /// every glyph quad into one `InstanceArray` draw call.
+    pub fn draw_text(&self, ctx: &mut Context, canvas: &mut Canvas, text: &str, pos: [f32; 2], scale: f32, color: Color) {
+        let mut batch = InstanceArray::new(ctx, self.atlas.clone());
+        let advance = (self.cell_width + self.advance_padding) * scale;
+        let mut cursor_x = pos[0];
+        for byte in text.bytes() {
+            if let Some(uv) = self.glyph_uv(byte) {
+                batch.push(
+                    DrawParam::default()
+                        .src(uv)
+                        .dest([cursor_x, pos[1]])
+                        .scale([scale, scale])
+                        .color(color),
+                );
+            }
+            cursor_x += advance;
+        }
+        canvas.draw(&batch, DrawParam::default());
+    }
+}
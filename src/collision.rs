@@ -0,0 +1,191 @@
+//! Quadtree spatial partition for trail/wall collision.
+//!
+//! Every cycle's trail can hold up to `TRAIL_MAX_LENGTH` points, and a naive
+//! check scans all of them every frame for every cycle. `CollisionGrid`
+//! indexes points in a recursive quadtree over the arena bounds so a query
+//! only has to descend into the nodes overlapping a small rect around the
+//! querying point, instead of touching every point ever pushed.
+
+use crate::{CELL_SIZE, GRID_HEIGHT, GRID_WIDTH};
+use ggez::graphics::Rect;
+use ggez::mint::Point2;
+use std::collections::VecDeque;
+
+/// A point is re-inserted at its would-be leaf when queried, so this only
+/// needs to be generous enough to cover the reaction distances callers
+/// actually query with. The largest is the Hard AI's `reaction_distance`
+/// (`CELL_SIZE * 8.0` in `main.rs`); pad it a little so an axis-aligned
+/// point sitting right at that distance isn't clipped by the query rect.
+const QUERY_RADIUS: f32 = CELL_SIZE * 8.0 + 4.0;
+
+/// Items per leaf before it `split`s into four children.
+const QUAD_BUCKET_CAPACITY: usize = 8;
+/// Leaves stop splitting at this depth even over capacity, so a pathological
+/// cluster of points at the same position can't recurse forever.
+const QUAD_MAX_DEPTH: u32 = 6;
+
+#[derive(Clone, Copy)]
+pub struct GridPoint {
+    pub position: Point2<f32>,
+    pub owner: usize,
+    seq: u64,
+}
+
+fn rects_intersect(a: &Rect, b: &Rect) -> bool {
+    a.x < b.x + b.w && a.x + a.w > b.x && a.y < b.y + b.h && a.y + a.h > b.y
+}
+
+/// One node of the quadtree: a bounding `Rect`, a bucket of items (only
+/// populated on leaves), and four children once split.
+struct QuadNode {
+    bounds: Rect,
+    depth: u32,
+    items: Vec<GridPoint>,
+    children: Option<Box<[QuadNode; 4]>>,
+}
+
+impl QuadNode {
+    fn new(bounds: Rect, depth: u32) -> Self {
+        QuadNode {
+            bounds,
+            depth,
+            items: Vec::new(),
+            children: None,
+        }
+    }
+
+    /// Which of the four children fully contains `position`, assuming it's
+    /// already known to be within `bounds`.
+    fn child_index(bounds: &Rect, position: Point2<f32>) -> usize {
+        let right = position.x >= bounds.x + bounds.w / 2.0;
+        let bottom = position.y >= bounds.y + bounds.h / 2.0;
+        match (right, bottom) {
+            (false, false) => 0,
+            (true, false) => 1,
+            (false, true) => 2,
+            (true, true) => 3,
+        }
+    }
+
+    /// Descend into the child fully containing `item`, splitting this node
+    /// first if it's a leaf over capacity.
+    fn insert(&mut self, item: GridPoint) {
+        if self.children.is_none() && self.items.len() >= QUAD_BUCKET_CAPACITY && self.depth < QUAD_MAX_DEPTH {
+            self.split();
+        }
+        if let Some(children) = &mut self.children {
+            let idx = Self::child_index(&self.bounds, item.position);
+            children[idx].insert(item);
+        } else {
+            self.items.push(item);
+        }
+    }
+
+    fn split(&mut self) {
+        let hw = self.bounds.w / 2.0;
+        let hh = self.bounds.h / 2.0;
+        let (x, y) = (self.bounds.x, self.bounds.y);
+        let mut children = [
+            QuadNode::new(Rect::new(x, y, hw, hh), self.depth + 1),
+            QuadNode::new(Rect::new(x + hw, y, hw, hh), self.depth + 1),
+            QuadNode::new(Rect::new(x, y + hh, hw, hh), self.depth + 1),
+            QuadNode::new(Rect::new(x + hw, y + hh, hw, hh), self.depth + 1),
+        ];
+        for item in self.items.drain(..) {
+            let idx = Self::child_index(&self.bounds, item.position);
+            children[idx].insert(item);
+        }
+        self.children = Some(Box::new(children));
+    }
+
+    /// Remove the item matching `owner`/`seq`, descending toward wherever
+    /// `position` would have been inserted.
+    fn remove(&mut self, position: Point2<f32>, owner: usize, seq: u64) -> bool {
+        if let Some(children) = &mut self.children {
+            let idx = Self::child_index(&self.bounds, position);
+            return children[idx].remove(position, owner, seq);
+        }
+        if let Some(i) = self.items.iter().position(|p| p.owner == owner && p.seq == seq) {
+            self.items.swap_remove(i);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Append every item from nodes whose bounds intersect `query_rect`.
+    fn query(&self, query_rect: &Rect, out: &mut Vec<GridPoint>) {
+        if !rects_intersect(&self.bounds, query_rect) {
+            return;
+        }
+        out.extend_from_slice(&self.items);
+        if let Some(children) = &self.children {
+            for child in children.iter() {
+                child.query(query_rect, out);
+            }
+        }
+    }
+}
+
+/// Owns every live trail point, indexed by a quadtree over the arena
+/// bounds. Kept in sync with each `LightCycle`'s `trail` `VecDeque` via
+/// `push_point`/`evict_oldest`, which mirror `push_back`/`pop_front` on the
+/// trail itself.
+pub struct CollisionGrid {
+    root: QuadNode,
+    owner_queues: Vec<VecDeque<(Point2<f32>, u64)>>,
+    next_seq: Vec<u64>,
+}
+
+impl CollisionGrid {
+    pub fn new(owner_count: usize) -> Self {
+        CollisionGrid {
+            root: QuadNode::new(Rect::new(0.0, 0.0, GRID_WIDTH, GRID_HEIGHT), 0),
+            owner_queues: vec![VecDeque::new(); owner_count],
+            next_seq: vec![0; owner_count],
+        }
+    }
+
+    /// Record a point just pushed onto `owner`'s trail.
+    pub fn push_point(&mut self, owner: usize, position: Point2<f32>) {
+        let seq = self.next_seq[owner];
+        self.next_seq[owner] += 1;
+        self.root.insert(GridPoint { position, owner, seq });
+        self.owner_queues[owner].push_back((position, seq));
+    }
+
+    /// Remove the oldest point still tracked for `owner`, mirroring a
+    /// `trail.pop_front()`.
+    pub fn evict_oldest(&mut self, owner: usize) {
+        let Some((position, seq)) = self.owner_queues[owner].pop_front() else {
+            return;
+        };
+        self.root.remove(position, owner, seq);
+    }
+
+    /// How many points have ever been pushed for `owner` (used to compute a
+    /// point's age relative to the owner's current trail).
+    pub fn pushed_count(&self, owner: usize) -> u64 {
+        self.next_seq[owner]
+    }
+
+    /// Every tracked point within `QUERY_RADIUS` of `position`, along with
+    /// how many pushes its owner has made since (its "age").
+    pub fn query_nearby(&self, position: Point2<f32>) -> Vec<(GridPoint, u64)> {
+        let query_rect = Rect::new(
+            position.x - QUERY_RADIUS,
+            position.y - QUERY_RADIUS,
+            QUERY_RADIUS * 2.0,
+            QUERY_RADIUS * 2.0,
+        );
+        let mut matches = Vec::new();
+        self.root.query(&query_rect, &mut matches);
+        matches
+            .into_iter()
+            .map(|point| {
+                let age = self.next_seq[point.owner] - point.seq;
+                (point, age)
+            })
+            .collect()
+    }
+}
@@ -5,20 +5,89 @@ use ggez::{
     mint::Point2,
     Context, ContextBuilder, GameResult,
 };
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use std::collections::VecDeque;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+mod arena;
+mod audio;
+mod bitmap_font;
+mod collision;
+mod nn;
+mod particles;
+mod replay;
+mod trainer;
+
+use collision::CollisionGrid;
+use arena::{Arena, ArenaLayout};
+use bitmap_font::BitmapFont;
+use particles::{EmitterLibrary, ParticleShape, ParticleSystem};
+const SELF_COLLISION_GRACE_POINTS: u64 = 10;
+
+const HARD_AI_GENOME_PATH: &str = "hard_ai.genome";
+const LAST_MATCH_REPLAY_PATH: &str = "last_match.replay";
+const SENSOR_MAX_DISTANCE: f32 = 400.0;
+const SENSOR_ANGLES_DEG: [f32; nn::SENSOR_COUNT - 1] = [-90.0, -45.0, 0.0, 45.0, 90.0];
+
+/// Single shared RNG source for a match (AI decisions, particle spawns,
+/// screen shake, ...), seeded once in `start_game` so a recorded seed plus
+/// input log is enough to reproduce a match exactly.
+struct GameRng(StdRng);
+
+impl GameRng {
+    fn seeded(seed: u64) -> Self {
+        GameRng(StdRng::seed_from_u64(seed))
+    }
+}
+
+impl std::ops::Deref for GameRng {
+    type Target = StdRng;
+    fn deref(&self) -> &StdRng {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for GameRng {
+    fn deref_mut(&mut self) -> &mut StdRng {
+        &mut self.0
+    }
+}
+
+/// A fresh, non-reproducible seed for a normal (non-replay) match.
+fn fresh_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
 
 const GRID_WIDTH: f32 = 1600.0;
 const GRID_HEIGHT: f32 = 1000.0;
+/// Size of the actual game window, which can be smaller than the
+/// `GRID_WIDTH`/`GRID_HEIGHT` arena so `Camera`s have room to pan. Two-human
+/// matches split this into left/right halves; every other mode uses it as
+/// one full-window viewport.
+const WINDOW_WIDTH: f32 = 1280.0;
+const WINDOW_HEIGHT: f32 = 800.0;
 const CELL_SIZE: f32 = 8.0;
 const CYCLE_SPEED: f32 = 3.0;
 const BOOST_SPEED: f32 = 6.0;
+/// Radians/second a `SteeringMode::FreeAngle` cycle turns at while a turn
+/// key is held.
+const FREE_TURN_RATE: f32 = std::f32::consts::PI;
+/// How far the NN "Hard" AI's discrete turn decision rotates a `FreeAngle`
+/// heading per tick, i.e. `FREE_TURN_RATE` over one fixed `dt = 1.0 / 60.0` frame.
+const AI_FREE_TURN_STEP: f32 = FREE_TURN_RATE / 60.0;
 const TRAIL_MAX_LENGTH: usize = 15000;
 const CYCLE_WIDTH: f32 = 16.0;
 const CYCLE_HEIGHT: f32 = 24.0;
 const MAX_BOOST_ENERGY: f32 = 100.0;
 const BOOST_DRAIN_RATE: f32 = 40.0; // Energy per second
 const BOOST_RECHARGE_RATE: f32 = 15.0; // Energy per second
+/// Distance from the left/right edges where each player spawns, also used
+/// to anchor the arena generator's guaranteed-clear spawn area.
+const SPAWN_MARGIN: f32 = 200.0;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 enum Direction {
@@ -47,6 +116,90 @@ impl Direction {
                 | (Direction::Right, Direction::Left)
         )
     }
+
+    /// Heading angle in radians (0 = +x/Right, increasing clockwise on
+    /// screen to match the y-down coordinate system).
+    fn to_angle(&self) -> f32 {
+        match self {
+            Direction::Right => 0.0,
+            Direction::Down => std::f32::consts::FRAC_PI_2,
+            Direction::Left => std::f32::consts::PI,
+            Direction::Up => -std::f32::consts::FRAC_PI_2,
+        }
+    }
+}
+
+/// Relative turns on the 4-way grid, used to translate NN turn decisions
+/// (which are relative to current heading) into an absolute `Direction`.
+fn turn_left(direction: Direction) -> Direction {
+    match direction {
+        Direction::Up => Direction::Left,
+        Direction::Left => Direction::Down,
+        Direction::Down => Direction::Right,
+        Direction::Right => Direction::Up,
+    }
+}
+
+fn turn_right(direction: Direction) -> Direction {
+    match direction {
+        Direction::Up => Direction::Right,
+        Direction::Right => Direction::Down,
+        Direction::Down => Direction::Left,
+        Direction::Left => Direction::Up,
+    }
+}
+
+/// Normalize a heading to `[0, TAU)` so it doesn't grow without bound while
+/// a `FreeAngle` cycle turns continuously over a long match.
+fn wrap_angle(angle: f32) -> f32 {
+    angle.rem_euclid(std::f32::consts::TAU)
+}
+
+/// Cast a ray from `origin` at `angle` (radians) and return the distance to
+/// the nearest wall or trail point, stepping in `SENSOR_MAX_DISTANCE / 32`
+/// increments and clamped/normalized to `[0.0, 1.0]`.
+fn cast_ray(origin: Point2<f32>, angle: f32, grid: &CollisionGrid) -> f32 {
+    let (dx, dy) = (angle.cos(), angle.sin());
+    let steps = 32;
+    let step_len = SENSOR_MAX_DISTANCE / steps as f32;
+
+    for step in 1..=steps {
+        let dist = step as f32 * step_len;
+        let x = origin.x + dx * dist;
+        let y = origin.y + dy * dist;
+
+        if x < 0.0 || x >= GRID_WIDTH || y < 0.0 || y >= GRID_HEIGHT {
+            return dist / SENSOR_MAX_DISTANCE;
+        }
+
+        let probe = Point2 { x, y };
+        for (point, _age) in grid.query_nearby(probe) {
+            let d = ((x - point.position.x).powi(2) + (y - point.position.y).powi(2)).sqrt();
+            if d < CELL_SIZE {
+                return dist / SENSOR_MAX_DISTANCE;
+            }
+        }
+    }
+
+    1.0
+}
+
+/// Build the sensor vector fed to the NN policy: normalized raycast
+/// distances in the forward/left/right/diagonal directions relative to
+/// `base_angle` (radians), followed by normalized `boost_energy`.
+fn cast_sensors(
+    position: Point2<f32>,
+    base_angle: f32,
+    boost_energy: f32,
+    grid: &CollisionGrid,
+) -> [f32; nn::SENSOR_COUNT] {
+    let mut sensors = [0.0f32; nn::SENSOR_COUNT];
+    for (i, offset_deg) in SENSOR_ANGLES_DEG.iter().enumerate() {
+        let angle = base_angle + offset_deg.to_radians();
+        sensors[i] = cast_ray(position, angle, grid);
+    }
+    sensors[nn::SENSOR_COUNT - 1] = boost_energy / MAX_BOOST_ENERGY;
+    sensors
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -62,70 +215,60 @@ enum AIDifficulty {
     Hard,
 }
 
-struct Explosion {
-    _position: Point2<f32>,
-    particles: Vec<Particle>,
-    time: f32,
+/// `Grid` is the original four-way-locked movement. `FreeAngle` lets a
+/// cycle hold any heading, turning continuously at `FREE_TURN_RATE` instead
+/// of snapping between `Direction`s.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SteeringMode {
+    Grid,
+    FreeAngle,
 }
 
-struct Particle {
-    position: Point2<f32>,
-    velocity: Point2<f32>,
-    lifetime: f32,
-    color: Color,
+/// Offsets (in degrees) the heuristic AI samples around its current heading
+/// when looking for a safe `FreeAngle` turn, mirroring `SENSOR_ANGLES_DEG`.
+const AI_FREE_HEADING_OFFSETS_DEG: [f32; 5] = [-90.0, -45.0, 0.0, 45.0, 90.0];
+
+/// Snapshot of one frame's `ai_update` decision for a computer cycle,
+/// recorded instead of discarded so the F3 debug overlay can show why the
+/// AI turned where it did. Candidate fields hold whichever of
+/// `safe_dirs`/`safe_headings` matches the cycle's `steering_mode`; the
+/// other stays empty.
+#[derive(Clone, Debug)]
+struct AiDebugInfo {
+    look_ahead_point: Point2<f32>,
+    should_turn: bool,
+    /// The trail/wall point that triggered `should_turn`, if any.
+    triggering_point: Option<Point2<f32>>,
+    safe_dirs: Vec<Direction>,
+    safe_headings: Vec<f32>,
+    /// Hard AI's open-space score per candidate, parallel to whichever of
+    /// `safe_dirs`/`safe_headings` is populated.
+    open_space_scores: Vec<f32>,
 }
 
-impl Explosion {
-    fn new(position: Point2<f32>, color: Color) -> Self {
-        let mut rng = rand::thread_rng();
-        let particles = (0..50)
-            .map(|_| {
-                let angle = rng.gen_range(0.0..std::f32::consts::TAU);
-                let speed = rng.gen_range(50.0..200.0);
-                Particle {
-                    position,
-                    velocity: Point2 {
-                        x: angle.cos() * speed,
-                        y: angle.sin() * speed,
-                    },
-                    lifetime: rng.gen_range(0.5..1.5),
-                    color: Color::new(
-                        color.r,
-                        color.g,
-                        color.b,
-                        rng.gen_range(0.5..1.0),
-                    ),
-                }
-            })
-            .collect();
-
-        Explosion {
-            _position: position,
-            particles,
-            time: 0.0,
+impl Default for AiDebugInfo {
+    fn default() -> Self {
+        AiDebugInfo {
+            look_ahead_point: Point2 { x: 0.0, y: 0.0 },
+            should_turn: false,
+            triggering_point: None,
+            safe_dirs: Vec::new(),
+            safe_headings: Vec::new(),
+            open_space_scores: Vec::new(),
         }
     }
-
-    fn update(&mut self, dt: f32) {
-        self.time += dt;
-        for particle in &mut self.particles {
-            particle.position.x += particle.velocity.x * dt;
-            particle.position.y += particle.velocity.y * dt;
-            particle.lifetime -= dt;
-            particle.velocity.x *= 0.98;
-            particle.velocity.y *= 0.98;
-        }
-        self.particles.retain(|p| p.lifetime > 0.0);
-    }
-
-    fn is_finished(&self) -> bool {
-        self.particles.is_empty()
-    }
 }
 
 struct LightCycle {
     position: Point2<f32>,
     direction: Direction,
+    /// Continuous heading (radians), used instead of `direction` when
+    /// `steering_mode` is `FreeAngle`. Kept in sync with `direction` even
+    /// in `Grid` mode so switching modes mid-match would pick up cleanly.
+    heading: f32,
+    /// Turn input for `FreeAngle` human control: -1 left, 0 none, 1 right.
+    turning: i8,
+    steering_mode: SteeringMode,
     trail: VecDeque<Point2<f32>>,
     color: Color,
     alive: bool,
@@ -134,7 +277,17 @@ struct LightCycle {
     boost_energy: f32,
     is_boosting: bool,
     boost_key: Option<KeyCode>,
+    /// Fractional-particle carry for the boost trail's continuous emitter;
+    /// see `ParticleSystem::emit_continuous`.
+    boost_particle_accum: f32,
     ai_difficulty: AIDifficulty,
+    ai_net: Option<nn::FeedForwardNet>,
+    debug_info: AiDebugInfo,
+    /// Set when `alive` turns false because this cycle hit the arena
+    /// boundary or an interior wall, rather than a trail. Read once by
+    /// `step` right after the death to decide whether to emit a
+    /// `"wall_spark"` burst alongside the usual explosion.
+    hit_wall: bool,
 }
 
 impl LightCycle {
@@ -147,10 +300,15 @@ impl LightCycle {
         controls: Option<(KeyCode, KeyCode, KeyCode, KeyCode)>,
         boost_key: Option<KeyCode>,
         ai_difficulty: AIDifficulty,
+        ai_net: Option<nn::FeedForwardNet>,
+        steering_mode: SteeringMode,
     ) -> Self {
         LightCycle {
             position: Point2 { x, y },
             direction,
+            heading: direction.to_angle(),
+            turning: 0,
+            steering_mode,
             trail: VecDeque::new(),
             color,
             alive: true,
@@ -159,11 +317,15 @@ impl LightCycle {
             boost_energy: MAX_BOOST_ENERGY,
             is_boosting: false,
             boost_key,
+            boost_particle_accum: 0.0,
             ai_difficulty,
+            ai_net,
+            debug_info: AiDebugInfo::default(),
+            hit_wall: false,
         }
     }
 
-    fn update(&mut self, dt: f32, all_trails: &[VecDeque<Point2<f32>>], own_index: usize) {
+    fn update(&mut self, dt: f32, grid: &mut CollisionGrid, own_index: usize, arena_owner: usize) {
         if !self.alive {
             return;
         }
@@ -179,11 +341,17 @@ impl LightCycle {
         }
 
         let speed = if self.is_boosting { BOOST_SPEED } else { CYCLE_SPEED };
-        let velocity = match self.direction {
-            Direction::Up => (0.0, -speed),
-            Direction::Down => (0.0, speed),
-            Direction::Left => (-speed, 0.0),
-            Direction::Right => (speed, 0.0),
+        let velocity = match self.steering_mode {
+            SteeringMode::Grid => match self.direction {
+                Direction::Up => (0.0, -speed),
+                Direction::Down => (0.0, speed),
+                Direction::Left => (-speed, 0.0),
+                Direction::Right => (speed, 0.0),
+            },
+            SteeringMode::FreeAngle => {
+                self.heading = wrap_angle(self.heading + self.turning as f32 * FREE_TURN_RATE * dt);
+                (self.heading.cos() * speed, self.heading.sin() * speed)
+            }
         };
         let old_pos = self.position;
         
@@ -202,10 +370,12 @@ impl LightCycle {
                 y: old_pos.y + (self.position.y - old_pos.y) * t,
             };
             self.trail.push_back(interpolated);
+            grid.push_point(own_index, interpolated);
         }
 
         if self.trail.len() > TRAIL_MAX_LENGTH {
             self.trail.pop_front();
+            grid.evict_oldest(own_index);
         }
 
         // Check wall collision
@@ -215,36 +385,86 @@ impl LightCycle {
             || self.position.y >= GRID_HEIGHT
         {
             self.alive = false;
+            self.hit_wall = true;
             return;
         }
 
-        // Check trail collision
-        for (i, trail) in all_trails.iter().enumerate() {
-            let check_range = if i == own_index {
-                // For own trail, skip recent points to avoid self-collision on turns
-                trail.len().saturating_sub(10)
-            } else {
-                trail.len()
-            };
-
-            for point in trail.iter().take(check_range) {
-                let dist = ((self.position.x - point.x).powi(2) + 
-                           (self.position.y - point.y).powi(2)).sqrt();
-                if dist < CELL_SIZE {
-                    self.alive = false;
-                    return;
-                }
+        // Check trail collision: only the cell the cycle is in and its
+        // eight neighbors need to be scanned, instead of every trail point.
+        for (point, age) in grid.query_nearby(self.position) {
+            if point.owner == own_index && age < SELF_COLLISION_GRACE_POINTS {
+                // Skip recent own points to avoid self-collision on turns.
+                continue;
+            }
+            let dist = ((self.position.x - point.position.x).powi(2)
+                + (self.position.y - point.position.y).powi(2))
+            .sqrt();
+            if dist < CELL_SIZE {
+                self.alive = false;
+                self.hit_wall = point.owner == arena_owner;
+                return;
             }
         }
     }
 
-    fn ai_update(&mut self, all_trails: &[VecDeque<Point2<f32>>], _own_index: usize) {
+    fn ai_update(&mut self, grid: &CollisionGrid, _own_index: usize, rng: &mut GameRng) {
         if self.player_type != PlayerType::Computer || !self.alive {
             return;
         }
 
-        let mut rng = rand::thread_rng();
-        
+        let current_heading = match self.steering_mode {
+            SteeringMode::Grid => self.direction.to_angle(),
+            SteeringMode::FreeAngle => self.heading,
+        };
+
+        if self.ai_difficulty == AIDifficulty::Hard {
+            if let Some(net) = &self.ai_net {
+                let sensors = cast_sensors(self.position, current_heading, self.boost_energy, grid);
+                let outputs = net.forward(&sensors);
+                let (decision, boost) = nn::decide(&outputs);
+                match self.steering_mode {
+                    SteeringMode::Grid => {
+                        self.direction = match decision {
+                            nn::Decision::Straight => self.direction,
+                            nn::Decision::TurnLeft => turn_left(self.direction),
+                            nn::Decision::TurnRight => turn_right(self.direction),
+                        };
+                    }
+                    SteeringMode::FreeAngle => {
+                        let delta = match decision {
+                            nn::Decision::Straight => 0.0,
+                            nn::Decision::TurnLeft => -AI_FREE_TURN_STEP,
+                            nn::Decision::TurnRight => AI_FREE_TURN_STEP,
+                        };
+                        self.heading = wrap_angle(self.heading + delta);
+                    }
+                }
+                self.is_boosting = boost && self.boost_energy > 10.0;
+                // Mirror the heuristic path's candidate-heading/open-space
+                // overlay data using the NN's own sensor rays (the angles
+                // `cast_sensors` just cast and fed it), instead of leaving
+                // it at `AiDebugInfo::default()` and rendering an
+                // always-green, info-free probe.
+                self.debug_info = AiDebugInfo {
+                    look_ahead_point: Point2 {
+                        x: self.position.x + current_heading.cos() * 40.0,
+                        y: self.position.y + current_heading.sin() * 40.0,
+                    },
+                    should_turn: decision != nn::Decision::Straight,
+                    safe_headings: SENSOR_ANGLES_DEG
+                        .iter()
+                        .map(|offset_deg| current_heading + offset_deg.to_radians())
+                        .collect(),
+                    open_space_scores: sensors[..nn::SENSOR_COUNT - 1]
+                        .iter()
+                        .map(|normalized| normalized * SENSOR_MAX_DISTANCE)
+                        .collect(),
+                    ..AiDebugInfo::default()
+                };
+                return;
+            }
+        }
+
         // Adjust AI parameters based on difficulty
         let (look_ahead, reaction_distance, turn_chance, boost_threshold, boost_chance) = match self.ai_difficulty {
             AIDifficulty::Easy => (20.0, CELL_SIZE * 3.0, 5, 30.0, 1),
@@ -253,111 +473,189 @@ impl LightCycle {
         };
 
         // Check if we need to turn
-        let current_velocity = self.direction.to_velocity();
-        let future_x = self.position.x + current_velocity.0 * look_ahead;
-        let future_y = self.position.y + current_velocity.1 * look_ahead;
+        let future_x = self.position.x + current_heading.cos() * CYCLE_SPEED * look_ahead;
+        let future_y = self.position.y + current_heading.sin() * CYCLE_SPEED * look_ahead;
 
         let mut should_turn = false;
-        
+        self.debug_info.triggering_point = None;
+        self.debug_info.safe_dirs.clear();
+        self.debug_info.safe_headings.clear();
+        self.debug_info.open_space_scores.clear();
+
         // Check for wall collision
-        if future_x < 10.0 || future_x >= GRID_WIDTH - 10.0 
+        if future_x < 10.0 || future_x >= GRID_WIDTH - 10.0
             || future_y < 10.0 || future_y >= GRID_HEIGHT - 10.0 {
             should_turn = true;
         }
 
         // Check for trail collision
         if !should_turn {
-            for (_i, trail) in all_trails.iter().enumerate() {
-                for point in trail.iter() {
-                    let dist_to_future = ((future_x - point.x).powi(2) + 
-                                         (future_y - point.y).powi(2)).sqrt();
-                    if dist_to_future < reaction_distance {
-                        should_turn = true;
-                        break;
-                    }
-                }
-                if should_turn {
+            let future_point = Point2 { x: future_x, y: future_y };
+            for (point, _age) in grid.query_nearby(future_point) {
+                let dist_to_future = ((future_x - point.position.x).powi(2) +
+                                     (future_y - point.position.y).powi(2)).sqrt();
+                if dist_to_future < reaction_distance {
+                    should_turn = true;
+                    self.debug_info.triggering_point = Some(point.position);
                     break;
                 }
             }
         }
 
-        if should_turn {
-            let possible_dirs = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
-            let mut safe_dirs = Vec::new();
+        self.debug_info.look_ahead_point = Point2 { x: future_x, y: future_y };
+        self.debug_info.should_turn = should_turn;
 
-            for dir in &possible_dirs {
-                if dir.is_opposite(&self.direction) {
-                    continue;
-                }
+        if should_turn {
+            match self.steering_mode {
+                SteeringMode::Grid => {
+                    let possible_dirs = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+                    let mut safe_dirs = Vec::new();
+
+                    for dir in &possible_dirs {
+                        if dir.is_opposite(&self.direction) {
+                            continue;
+                        }
 
-                let test_velocity = dir.to_velocity();
-                let test_x = self.position.x + test_velocity.0 * look_ahead;
-                let test_y = self.position.y + test_velocity.1 * look_ahead;
-
-                // Check if this direction is safe
-                let mut is_safe = test_x >= 10.0 && test_x < GRID_WIDTH - 10.0 
-                    && test_y >= 10.0 && test_y < GRID_HEIGHT - 10.0;
-
-                if is_safe {
-                    for trail in all_trails.iter() {
-                        for point in trail.iter() {
-                            let dist = ((test_x - point.x).powi(2) + 
-                                       (test_y - point.y).powi(2)).sqrt();
-                            if dist < reaction_distance {
-                                is_safe = false;
-                                break;
+                        let test_velocity = dir.to_velocity();
+                        let test_x = self.position.x + test_velocity.0 * look_ahead;
+                        let test_y = self.position.y + test_velocity.1 * look_ahead;
+
+                        // Check if this direction is safe
+                        let mut is_safe = test_x >= 10.0 && test_x < GRID_WIDTH - 10.0
+                            && test_y >= 10.0 && test_y < GRID_HEIGHT - 10.0;
+
+                        if is_safe {
+                            let test_point = Point2 { x: test_x, y: test_y };
+                            for (point, _age) in grid.query_nearby(test_point) {
+                                let dist = ((test_x - point.position.x).powi(2) +
+                                           (test_y - point.position.y).powi(2)).sqrt();
+                                if dist < reaction_distance {
+                                    is_safe = false;
+                                    break;
+                                }
                             }
                         }
-                        if !is_safe {
-                            break;
+
+                        if is_safe {
+                            safe_dirs.push(*dir);
                         }
                     }
-                }
 
-                if is_safe {
-                    safe_dirs.push(*dir);
+                    self.debug_info.safe_dirs = safe_dirs.clone();
+
+                    if !safe_dirs.is_empty() {
+                        // Hard AI chooses more optimal paths
+                        if self.ai_difficulty == AIDifficulty::Hard && safe_dirs.len() > 1 {
+                            // Choose direction with most open space
+                            let mut best_dir = safe_dirs[0];
+                            let mut max_space = 0.0;
+                            let mut scores = Vec::new();
+
+                            for dir in &safe_dirs {
+                                let test_velocity = dir.to_velocity();
+                                let mut space = 0.0;
+                                for i in 1..10 {
+                                    let check_x = self.position.x + test_velocity.0 * (i as f32 * 10.0);
+                                    let check_y = self.position.y + test_velocity.1 * (i as f32 * 10.0);
+                                    if check_x >= 0.0 && check_x < GRID_WIDTH && check_y >= 0.0 && check_y < GRID_HEIGHT {
+                                        space += 10.0;
+                                    } else {
+                                        break;
+                                    }
+                                }
+                                scores.push(space);
+                                if space > max_space {
+                                    max_space = space;
+                                    best_dir = *dir;
+                                }
+                            }
+                            self.debug_info.open_space_scores = scores;
+                            self.direction = best_dir;
+                        } else {
+                            self.direction = safe_dirs[rng.gen_range(0..safe_dirs.len())];
+                        }
+                    }
                 }
-            }
-
-            if !safe_dirs.is_empty() {
-                // Hard AI chooses more optimal paths
-                if self.ai_difficulty == AIDifficulty::Hard && safe_dirs.len() > 1 {
-                    // Choose direction with most open space
-                    let mut best_dir = safe_dirs[0];
-                    let mut max_space = 0.0;
-                    
-                    for dir in &safe_dirs {
-                        let test_velocity = dir.to_velocity();
-                        let mut space = 0.0;
-                        for i in 1..10 {
-                            let check_x = self.position.x + test_velocity.0 * (i as f32 * 10.0);
-                            let check_y = self.position.y + test_velocity.1 * (i as f32 * 10.0);
-                            if check_x >= 0.0 && check_x < GRID_WIDTH && check_y >= 0.0 && check_y < GRID_HEIGHT {
-                                space += 10.0;
-                            } else {
-                                break;
+                SteeringMode::FreeAngle => {
+                    // Sample candidate headings around the current one,
+                    // same spirit as the grid search above but continuous.
+                    let mut safe_headings = Vec::new();
+
+                    for offset_deg in AI_FREE_HEADING_OFFSETS_DEG {
+                        let candidate = current_heading + offset_deg.to_radians();
+                        let test_x = self.position.x + candidate.cos() * CYCLE_SPEED * look_ahead;
+                        let test_y = self.position.y + candidate.sin() * CYCLE_SPEED * look_ahead;
+
+                        let mut is_safe = test_x >= 10.0 && test_x < GRID_WIDTH - 10.0
+                            && test_y >= 10.0 && test_y < GRID_HEIGHT - 10.0;
+
+                        if is_safe {
+                            let test_point = Point2 { x: test_x, y: test_y };
+                            for (point, _age) in grid.query_nearby(test_point) {
+                                let dist = ((test_x - point.position.x).powi(2) +
+                                           (test_y - point.position.y).powi(2)).sqrt();
+                                if dist < reaction_distance {
+                                    is_safe = false;
+                                    break;
+                                }
                             }
                         }
-                        if space > max_space {
-                            max_space = space;
-                            best_dir = *dir;
+
+                        if is_safe {
+                            safe_headings.push(candidate);
+                        }
+                    }
+
+                    self.debug_info.safe_headings = safe_headings.clone();
+
+                    if !safe_headings.is_empty() {
+                        if self.ai_difficulty == AIDifficulty::Hard && safe_headings.len() > 1 {
+                            let mut best_heading = safe_headings[0];
+                            let mut max_space = 0.0;
+                            let mut scores = Vec::new();
+
+                            for &heading in &safe_headings {
+                                let mut space = 0.0;
+                                for i in 1..10 {
+                                    let check_x = self.position.x + heading.cos() * CYCLE_SPEED * (i as f32 * 10.0);
+                                    let check_y = self.position.y + heading.sin() * CYCLE_SPEED * (i as f32 * 10.0);
+                                    if check_x >= 0.0 && check_x < GRID_WIDTH && check_y >= 0.0 && check_y < GRID_HEIGHT {
+                                        space += 10.0;
+                                    } else {
+                                        break;
+                                    }
+                                }
+                                scores.push(space);
+                                if space > max_space {
+                                    max_space = space;
+                                    best_heading = heading;
+                                }
+                            }
+                            self.debug_info.open_space_scores = scores;
+                            self.heading = wrap_angle(best_heading);
+                        } else {
+                            self.heading = wrap_angle(safe_headings[rng.gen_range(0..safe_headings.len())]);
                         }
                     }
-                    self.direction = best_dir;
-                } else {
-                    self.direction = safe_dirs[rng.gen_range(0..safe_dirs.len())];
                 }
             }
         } else if rng.gen_range(0..100) < turn_chance {
             // Random turn occasionally for unpredictability
-            let possible_dirs = match self.direction {
-                Direction::Up | Direction::Down => vec![Direction::Left, Direction::Right],
-                Direction::Left | Direction::Right => vec![Direction::Up, Direction::Down],
-            };
-            self.direction = possible_dirs[rng.gen_range(0..possible_dirs.len())];
+            match self.steering_mode {
+                SteeringMode::Grid => {
+                    let possible_dirs = match self.direction {
+                        Direction::Up | Direction::Down => vec![Direction::Left, Direction::Right],
+                        Direction::Left | Direction::Right => vec![Direction::Up, Direction::Down],
+                    };
+                    self.direction = possible_dirs[rng.gen_range(0..possible_dirs.len())];
+                }
+                SteeringMode::FreeAngle => {
+                    let delta_deg = if rng.gen_bool(0.5) { -45.0_f32 } else { 45.0_f32 };
+                    self.heading = wrap_angle(current_heading + delta_deg.to_radians());
+                }
+            }
         }
-        
+
         // AI boost management
         if self.boost_energy > boost_threshold {
             // Use boost strategically
@@ -391,24 +689,40 @@ impl LightCycle {
             }
         }
 
-        // Handle direction (only on key press)
-        if pressed {
-            if let Some((up, down, left, right)) = self.controls {
-                let new_direction = if keycode == up {
-                    Some(Direction::Up)
-                } else if keycode == down {
-                    Some(Direction::Down)
-                } else if keycode == left {
-                    Some(Direction::Left)
-                } else if keycode == right {
-                    Some(Direction::Right)
-                } else {
-                    None
-                };
+        match self.steering_mode {
+            SteeringMode::Grid => {
+                // Handle direction (only on key press)
+                if pressed {
+                    if let Some((up, down, left, right)) = self.controls {
+                        let new_direction = if keycode == up {
+                            Some(Direction::Up)
+                        } else if keycode == down {
+                            Some(Direction::Down)
+                        } else if keycode == left {
+                            Some(Direction::Left)
+                        } else if keycode == right {
+                            Some(Direction::Right)
+                        } else {
+                            None
+                        };
 
-                if let Some(dir) = new_direction {
-                    if !dir.is_opposite(&self.direction) {
-                        self.direction = dir;
+                        if let Some(dir) = new_direction {
+                            if !dir.is_opposite(&self.direction) {
+                                self.direction = dir;
+                            }
+                        }
+                    }
+                }
+            }
+            SteeringMode::FreeAngle => {
+                // Holding left/right continuously rotates `heading` in
+                // `update`; releasing the currently-active turn key stops
+                // turning instead of snapping straight.
+                if let Some((_up, _down, left, right)) = self.controls {
+                    if keycode == left {
+                        self.turning = if pressed { -1 } else if self.turning == -1 { 0 } else { self.turning };
+                    } else if keycode == right {
+                        self.turning = if pressed { 1 } else if self.turning == 1 { 0 } else { self.turning };
                     }
                 }
             }
@@ -416,82 +730,122 @@ impl LightCycle {
     }
 }
 
-enum GameMode {
-    Menu,
-    Playing,
-    Paused,
-    GameOver { winner: String },
+/// A transition returned by an `AppState` in response to `update` or
+/// `handle_key`, applied by `GameState` to mutate the state stack.
+enum Transition {
+    /// Stay on the same state.
+    None,
+    /// Push a new state on top; the states beneath keep running (suspended
+    /// for `update`/`handle_key`, but still drawn) until it's popped.
+    Push(Box<dyn AppState>),
+    /// Pop the current state and resume whatever is now on top.
+    Pop,
+    /// Tear down the whole stack and start fresh with a single new state,
+    /// e.g. going from `Paused`/`GameOver` straight back to the menu.
+    Replace(Box<dyn AppState>),
 }
 
-struct TrailParticle {
-    position: Point2<f32>,
-    velocity: Point2<f32>,
-    lifetime: f32,
-    color: Color,
-}
-
-impl TrailParticle {
-    fn new(position: Point2<f32>, direction: Direction, color: Color) -> Self {
-        let mut rng = rand::thread_rng();
-        let base_vel = direction.to_velocity();
-        TrailParticle {
-            position,
-            velocity: Point2 {
-                x: -base_vel.0 * 0.5 + rng.gen_range(-10.0..10.0),
-                y: -base_vel.1 * 0.5 + rng.gen_range(-10.0..10.0),
-            },
-            lifetime: rng.gen_range(0.2..0.5),
-            color: Color::new(
-                color.r,
-                color.g,
-                color.b,
-                rng.gen_range(0.3..0.7),
-            ),
-        }
+/// One entry in `GameState`'s state stack (menu, a running match, a pause
+/// overlay, game over, ...). Only the top of the stack receives `update`
+/// and `handle_key`, but every state in the stack is drawn bottom-to-top,
+/// so an overlay like `PauseOverlayState` can sit on top of `PlayingState`
+/// and only paint the bits it adds instead of redrawing the whole scene.
+trait AppState {
+    /// Called once when the state is pushed onto the stack.
+    fn enter(&mut self, _world: &mut World) {}
+    /// Called once when the state is popped or replaced off the stack.
+    fn leave(&mut self, _world: &mut World) {}
+    fn update(&mut self, _world: &mut World, _ctx: &mut Context, _dt: f32) -> Transition {
+        Transition::None
     }
-    
-    fn update(&mut self, dt: f32) {
-        self.position.x += self.velocity.x * dt;
-        self.position.y += self.velocity.y * dt;
-        self.lifetime -= dt;
-        self.velocity.x *= 0.95;
-        self.velocity.y *= 0.95;
+    fn draw(&self, world: &mut World, ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult;
+    fn handle_key(&mut self, _world: &mut World, _keycode: KeyCode, _pressed: bool) -> Transition {
+        Transition::None
     }
 }
 
-struct GameState {
+/// Shared game data that every `AppState` can read and mutate: the match in
+/// progress (cycles, particles, arena, ...), persistent menu selections, and
+/// the seeded RNG/replay recording that make a match reproducible.
+struct World {
     cycles: Vec<LightCycle>,
-    explosions: Vec<Explosion>,
-    mode: GameMode,
+    particles: ParticleSystem,
+    emitter_library: EmitterLibrary,
     single_player: bool,
     ai_difficulty: AIDifficulty,
+    steering_mode: SteeringMode,
+    arena_layout: ArenaLayout,
+    arena: Arena,
+    debug_overlay: bool,
     screen_shake: f32,
-    trail_particles: Vec<TrailParticle>,
+    hard_ai_net: Option<nn::FeedForwardNet>,
+    rng: GameRng,
+    frame_count: u64,
+    recording: replay::Replay,
+    active_replay: Option<replay::Replay>,
+    collision_grid: CollisionGrid,
+    audio: audio::AudioEngine,
+    /// `None` if `BITMAP_FONT_PATH` couldn't be loaded, in which case
+    /// `draw_text` falls back to ggez's default `graphics::Text`.
+    font: Option<BitmapFont>,
 }
 
-impl GameState {
-    fn new() -> Self {
-        GameState {
+impl World {
+    fn new(ctx: &mut Context) -> Self {
+        World {
             cycles: Vec::new(),
-            explosions: Vec::new(),
-            mode: GameMode::Menu,
+            particles: ParticleSystem::new(),
+            emitter_library: EmitterLibrary::load(particles::EMITTER_CONFIG_PATH),
             single_player: true,
             ai_difficulty: AIDifficulty::Medium,
+            steering_mode: SteeringMode::Grid,
+            arena_layout: ArenaLayout::Empty,
+            arena: Arena { segments: Vec::new(), blocks: Vec::new() },
+            debug_overlay: false,
             screen_shake: 0.0,
-            trail_particles: Vec::new(),
+            hard_ai_net: trainer::load_genome(HARD_AI_GENOME_PATH)
+                .map(|genome| nn::FeedForwardNet::from_genome(nn::SENSOR_COUNT, nn::HIDDEN_COUNT, nn::OUTPUT_COUNT, genome)),
+            rng: GameRng::seeded(fresh_seed()),
+            frame_count: 0,
+            recording: replay::Replay::new(0, true, AIDifficulty::Medium, SteeringMode::Grid, ArenaLayout::Empty),
+            active_replay: None,
+            collision_grid: CollisionGrid::new(0),
+            audio: audio::AudioEngine::new(),
+            font: BitmapFont::load(ctx, bitmap_font::BITMAP_FONT_PATH),
         }
     }
 
     fn start_game(&mut self, single_player: bool) {
+        self.start_game_with_seed(single_player, fresh_seed());
+        self.active_replay = None;
+    }
+
+    /// Replay a previously recorded match: same seed, same match setup
+    /// (player count, AI difficulty, steering mode, arena layout), and the
+    /// recorded human inputs are injected at their original frames instead
+    /// of coming from real key events. Restores the recorded setup onto
+    /// `World` first so it's actually the match that was recorded, not
+    /// whatever the menu happens to be set to right now.
+    fn start_replay(&mut self, replay: replay::Replay) {
+        self.ai_difficulty = replay.ai_difficulty;
+        self.steering_mode = replay.steering_mode;
+        self.arena_layout = replay.arena_layout;
+        self.start_game_with_seed(replay.single_player, replay.seed);
+        self.active_replay = Some(replay);
+    }
+
+    fn start_game_with_seed(&mut self, single_player: bool, seed: u64) {
         self.cycles.clear();
-        self.explosions.clear();
-        self.trail_particles.clear();
+        self.particles.clear();
         self.screen_shake = 0.0;
         self.single_player = single_player;
-        
+        self.rng = GameRng::seeded(seed);
+        self.frame_count = 0;
+        self.recording = replay::Replay::new(seed, single_player, self.ai_difficulty, self.steering_mode, self.arena_layout);
+
         // Player 1 (WASD controls, LShift for boost)
         self.cycles.push(LightCycle::new(
-            200.0,
+            SPAWN_MARGIN,
             GRID_HEIGHT / 2.0,
             Direction::Right,
             Color::from_rgb(0, 255, 255), // Cyan
@@ -499,11 +853,13 @@ impl GameState {
             Some((KeyCode::W, KeyCode::S, KeyCode::A, KeyCode::D)),
             Some(KeyCode::LShift),
             AIDifficulty::Medium, // Not used for human players
+            None,
+            self.steering_mode,
         ));
 
         // Player 2 or Computer (Arrow keys, RShift for boost)
         self.cycles.push(LightCycle::new(
-            GRID_WIDTH - 200.0,
+            GRID_WIDTH - SPAWN_MARGIN,
             GRID_HEIGHT / 2.0,
             Direction::Left,
             Color::from_rgb(255, 165, 0), // Orange
@@ -515,674 +871,1153 @@ impl GameState {
             },
             if single_player { None } else { Some(KeyCode::RShift) },
             self.ai_difficulty,
+            if single_player && self.ai_difficulty == AIDifficulty::Hard {
+                self.hard_ai_net.clone()
+            } else {
+                None
+            },
+            self.steering_mode,
         ));
 
-        self.mode = GameMode::Playing;
+        // Generated from the same seeded `GameRng` the cycles and AI use, so
+        // a replay's arena matches the original match exactly. The two spawn
+        // points are passed through so layouts that need it (e.g. `Caves`)
+        // can guarantee clear ground under each player and a corridor
+        // between them.
+        let spawn_a = Point2 { x: SPAWN_MARGIN, y: GRID_HEIGHT / 2.0 };
+        let spawn_b = Point2 { x: GRID_WIDTH - SPAWN_MARGIN, y: GRID_HEIGHT / 2.0 };
+        self.arena = Arena::generate(self.arena_layout, &mut self.rng, spawn_a, spawn_b);
+        self.collision_grid = CollisionGrid::new(self.cycles.len() + 1);
+        self.arena.populate_grid(&mut self.collision_grid, self.cycles.len());
+        self.audio.reset(self.cycles.len());
+
+        // A small burst at each spawn point so a fresh match doesn't just
+        // pop the cycles in silently.
+        for cycle in &self.cycles {
+            self.particles.emit(
+                &self.emitter_library,
+                "spawn_burst",
+                cycle.position,
+                0.0,
+                cycle.color,
+                &mut self.rng,
+            );
+        }
     }
 
-    fn check_game_over(&mut self) {
+    /// If the match just ended, save the replay (for a live match; a replay
+    /// already being watched isn't re-saved) and return the winner text.
+    fn finish_if_game_over(&mut self) -> Option<String> {
         let alive_count = self.cycles.iter().filter(|c| c.alive).count();
-        
-        if alive_count <= 1 {
-            let winner = if alive_count == 0 {
-                "Draw!".to_string()
-            } else {
-                let winner_idx = self.cycles.iter().position(|c| c.alive).unwrap();
-                match winner_idx {
-                    0 => "Player 1 Wins!".to_string(),
-                    1 => if self.single_player { 
-                        "Computer Wins!".to_string() 
-                    } else { 
-                        "Player 2 Wins!".to_string() 
-                    },
-                    _ => "Unknown".to_string(),
-                }
-            };
-            self.mode = GameMode::GameOver { winner };
+
+        if alive_count > 1 {
+            return None;
         }
+
+        let winner = if alive_count == 0 {
+            "Draw!".to_string()
+        } else {
+            let winner_idx = self.cycles.iter().position(|c| c.alive).unwrap();
+            match winner_idx {
+                0 => "Player 1 Wins!".to_string(),
+                1 => if self.single_player { 
+                    "Computer Wins!".to_string() 
+                } else { 
+                    "Player 2 Wins!".to_string() 
+                },
+                _ => "Unknown".to_string(),
+            }
+        };
+        if self.active_replay.is_none() {
+            if let Err(e) = self.recording.save(LAST_MATCH_REPLAY_PATH) {
+                eprintln!("failed to save match replay to {LAST_MATCH_REPLAY_PATH}: {e}");
+            }
+        }
+        Some(winner)
     }
-}
 
-impl EventHandler for GameState {
-    fn update(&mut self, _ctx: &mut Context) -> GameResult {
-        match self.mode {
-            GameMode::Playing => {
-                let dt = 1.0 / 60.0;
-                
-                // AI updates
-                let all_trails: Vec<_> = self.cycles.iter().map(|c| c.trail.clone()).collect();
-                for (i, cycle) in self.cycles.iter_mut().enumerate() {
-                    cycle.ai_update(&all_trails, i);
+    /// Advance one fixed-`dt` tick. Shared by `PlayingState` and
+    /// `ReplayState` so a replayed match runs through exactly the same code
+    /// path as a live one.
+    fn step(&mut self, dt: f32) {
+        // AI updates
+        for (i, cycle) in self.cycles.iter_mut().enumerate() {
+            let was_boosting = cycle.is_boosting;
+            cycle.ai_update(&self.collision_grid, i, &mut self.rng);
+            if !was_boosting && cycle.is_boosting {
+                self.audio.trigger_boost_start();
+            }
+        }
+
+        // Movement updates
+        let arena_owner = self.cycles.len();
+        for (i, cycle) in self.cycles.iter_mut().enumerate() {
+            let was_alive = cycle.alive;
+            cycle.update(dt, &mut self.collision_grid, i, arena_owner);
+            self.audio.set_hum(i, cycle.alive, cycle.is_boosting);
+
+            // Create explosion when cycle dies
+            if was_alive && !cycle.alive {
+                self.particles.emit(
+                    &self.emitter_library,
+                    "explosion",
+                    cycle.position,
+                    0.0,
+                    cycle.color,
+                    &mut self.rng,
+                );
+                self.screen_shake = 20.0; // Add screen shake on collision
+                self.audio.trigger_explosion();
+                if cycle.hit_wall {
+                    self.particles.emit(
+                        &self.emitter_library,
+                        "wall_spark",
+                        cycle.position,
+                        0.0,
+                        cycle.color,
+                        &mut self.rng,
+                    );
                 }
+            }
 
-                // Movement updates
-                let all_trails: Vec<_> = self.cycles.iter().map(|c| c.trail.clone()).collect();
-                for (i, cycle) in self.cycles.iter_mut().enumerate() {
-                    let was_alive = cycle.alive;
-                    cycle.update(dt, &all_trails, i);
-                    
-                    // Create explosion when cycle dies
-                    if was_alive && !cycle.alive {
-                        self.explosions.push(Explosion::new(cycle.position, cycle.color));
-                        self.screen_shake = 20.0; // Add screen shake on collision
-                    }
-                    
-                    // Create trail particles for boosting cycles
-                    if cycle.alive && cycle.is_boosting {
-                        let mut rng = rand::thread_rng();
-                        if rng.gen_range(0..100) < 30 { // 30% chance to spawn particle
-                            self.trail_particles.push(TrailParticle::new(
-                                cycle.position,
-                                cycle.direction,
-                                cycle.color,
-                            ));
-                        }
+            // Create trail particles for boosting cycles
+            if cycle.alive && cycle.is_boosting {
+                let trailing_heading = cycle.direction.to_angle() + std::f32::consts::PI;
+                self.particles.emit_continuous(
+                    &self.emitter_library,
+                    "boost_trail",
+                    cycle.position,
+                    trailing_heading,
+                    cycle.color,
+                    dt,
+                    &mut self.rng,
+                    &mut cycle.boost_particle_accum,
+                );
+            }
+        }
+
+        // Update particles
+        self.particles.update(dt);
+
+        // Update screen shake
+        if self.screen_shake > 0.0 {
+            self.screen_shake = (self.screen_shake - dt * 50.0).max(0.0);
+        }
+
+        self.frame_count += 1;
+    }
+
+    /// Apply every recorded input tagged with the current frame, then step.
+    fn step_replay(&mut self, dt: f32) {
+        if let Some(replay) = &self.active_replay {
+            let frame = self.frame_count;
+            let events: Vec<_> = replay
+                .events
+                .iter()
+                .filter(|e| e.frame == frame)
+                .copied()
+                .collect();
+            for event in events {
+                for cycle in &mut self.cycles {
+                    let was_boosting = cycle.is_boosting;
+                    cycle.handle_input(event.keycode, event.pressed);
+                    if !was_boosting && cycle.is_boosting {
+                        self.audio.trigger_boost_start();
                     }
                 }
+            }
+        }
+        self.step(dt);
+    }
 
-                // Update explosions
-                for explosion in &mut self.explosions {
-                    explosion.update(dt);
-                }
-                self.explosions.retain(|e| !e.is_finished());
-                
-                // Update trail particles
-                for particle in &mut self.trail_particles {
-                    particle.update(dt);
-                }
-                self.trail_particles.retain(|p| p.lifetime > 0.0);
-                
-                // Update screen shake
-                if self.screen_shake > 0.0 {
-                    self.screen_shake = (self.screen_shake - dt * 50.0).max(0.0);
-                }
+    /// Synthesize and play this frame's audio: each cycle's engine hum plus
+    /// any boost/explosion one-shots triggered during `step`/`step_replay`.
+    fn mix_audio(&mut self, ctx: &mut Context) -> GameResult {
+        self.audio.mix_frame(ctx, &mut self.rng)
+    }
 
-                self.check_game_over();
-            }
-            GameMode::Paused => {
-                // Do nothing while paused
-            }
-            _ => {}
+    /// This frame's cameras: one per human player in two-player mode, split
+    /// left/right across the window, or a single full-window camera
+    /// otherwise (single player, or spectating a replay). Lets the
+    /// `GRID_WIDTH`/`GRID_HEIGHT` arena exceed `WINDOW_WIDTH`/`WINDOW_HEIGHT`
+    /// by panning each camera to follow the cycle it's assigned to.
+    fn cameras(&self) -> Vec<Camera> {
+        let humans: Vec<usize> = self
+            .cycles
+            .iter()
+            .enumerate()
+            .filter(|(_, cycle)| cycle.player_type == PlayerType::Human)
+            .map(|(i, _)| i)
+            .collect();
+
+        if humans.len() >= 2 {
+            let half_width = WINDOW_WIDTH / 2.0;
+            vec![
+                Camera::following(
+                    self.cycles[humans[0]].position,
+                    Rect::new(0.0, 0.0, half_width, WINDOW_HEIGHT),
+                    Some(humans[0]),
+                ),
+                Camera::following(
+                    self.cycles[humans[1]].position,
+                    Rect::new(half_width, 0.0, half_width, WINDOW_HEIGHT),
+                    Some(humans[1]),
+                ),
+            ]
+        } else {
+            let viewport = Rect::new(0.0, 0.0, WINDOW_WIDTH, WINDOW_HEIGHT);
+            let target = humans
+                .first()
+                .map(|&i| self.cycles[i].position)
+                .unwrap_or(Point2 { x: GRID_WIDTH / 2.0, y: GRID_HEIGHT / 2.0 });
+            vec![Camera::following(target, viewport, humans.first().copied())]
         }
-        Ok(())
     }
+}
 
-    fn draw(&mut self, ctx: &mut Context) -> GameResult {
-        let mut canvas = graphics::Canvas::from_frame(ctx, Color::BLACK);
+/// One player's view into the world: where it's centered, how zoomed in it
+/// is, and the screen-space rect it's drawn into (the left/right half of
+/// the window in split-screen, or the whole window otherwise). `follows` is
+/// the index into `World::cycles` whose boost bar belongs in this camera's
+/// viewport, if any.
+struct Camera {
+    center: Point2<f32>,
+    zoom: f32,
+    viewport: Rect,
+    follows: Option<usize>,
+}
 
-        match &self.mode {
-            GameMode::Menu => {
-                let title_text = graphics::Text::new("LIGHT CYCLE");
-                canvas.draw(
-                    &title_text,
-                    DrawParam::default()
-                        .dest([GRID_WIDTH / 2.0 - 200.0, 300.0])
-                        .color(Color::from_rgb(0, 255, 255))
-                        .scale([4.0, 4.0]),
-                );
+impl Camera {
+    /// A camera centered on `target`, clamped so its viewport never shows
+    /// past the edges of the `GRID_WIDTH`/`GRID_HEIGHT` world (or simply
+    /// centered on the arena, if the viewport is as big as the arena or
+    /// bigger).
+    fn following(target: Point2<f32>, viewport: Rect, follows: Option<usize>) -> Self {
+        let half_w = viewport.w / 2.0;
+        let half_h = viewport.h / 2.0;
+        let center = Point2 {
+            x: if GRID_WIDTH > viewport.w {
+                target.x.clamp(half_w, GRID_WIDTH - half_w)
+            } else {
+                GRID_WIDTH / 2.0
+            },
+            y: if GRID_HEIGHT > viewport.h {
+                target.y.clamp(half_h, GRID_HEIGHT - half_h)
+            } else {
+                GRID_HEIGHT / 2.0
+            },
+        };
+        Camera { center, zoom: 1.0, viewport, follows }
+    }
 
-                let sp_text = graphics::Text::new("Press 1 for Single Player");
-                canvas.draw(
-                    &sp_text,
-                    DrawParam::default()
-                        .dest([GRID_WIDTH / 2.0 - 120.0, 420.0])
-                        .color(Color::WHITE),
-                );
+    /// World-to-screen translation that places `center` at the middle of
+    /// `viewport`, for use as a `DrawParam::dest` offset alongside the
+    /// existing screen-shake offset.
+    fn offset(&self) -> Point2<f32> {
+        Point2 {
+            x: self.viewport.x + self.viewport.w / 2.0 - self.center.x * self.zoom,
+            y: self.viewport.y + self.viewport.h / 2.0 - self.center.y * self.zoom,
+        }
+    }
+}
 
-                let mp_text = graphics::Text::new("Press 2 for Two Players");
-                canvas.draw(
-                    &mp_text,
-                    DrawParam::default()
-                        .dest([GRID_WIDTH / 2.0 - 120.0, 460.0])
-                        .color(Color::WHITE),
-                );
-                
-                let diff_text = graphics::Text::new(format!("AI Difficulty: {:?} (Press D to change)", self.ai_difficulty));
-                canvas.draw(
-                    &diff_text,
-                    DrawParam::default()
-                        .dest([GRID_WIDTH / 2.0 - 160.0, 520.0])
-                        .color(match self.ai_difficulty {
-                            AIDifficulty::Easy => Color::from_rgb(100, 255, 100),
-                            AIDifficulty::Medium => Color::from_rgb(255, 255, 100),
-                            AIDifficulty::Hard => Color::from_rgb(255, 100, 100),
-                        }),
+/// Draw `text` at `pos`, scaled uniformly by `scale` and tinted `color`,
+/// via `world`'s loaded `BitmapFont`. Falls back to ggez's default
+/// `graphics::Text` if no atlas could be loaded (e.g. a missing asset
+/// file), matching `EmitterLibrary`'s missing-file fallback.
+fn draw_text(
+    world: &World,
+    ctx: &mut Context,
+    canvas: &mut graphics::Canvas,
+    text: &str,
+    pos: [f32; 2],
+    scale: f32,
+    color: Color,
+) {
+    match &world.font {
+        Some(font) => font.draw_text(ctx, canvas, text, pos, scale, color),
+        None => {
+            let rendered = graphics::Text::new(text);
+            canvas.draw(
+                &rendered,
+                DrawParam::default().dest(pos).color(color).scale([scale, scale]),
+            );
+        }
+    }
+}
+
+/// Draws the arena, trails, particles, cycles, HUD and (when toggled) the
+/// AI debug overlay. Shared by `PlayingState` and `ReplayState` so watching
+/// a replay looks exactly like the original match.
+fn draw_match_scene(world: &mut World, ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult {
+    // Apply screen shake
+    let shake_offset = if world.screen_shake > 0.0 {
+        Point2 {
+            x: world.rng.gen_range(-world.screen_shake..world.screen_shake),
+            y: world.rng.gen_range(-world.screen_shake..world.screen_shake),
+        }
+    } else {
+        Point2 { x: 0.0, y: 0.0 }
+    };
+
+    // Two-human matches get one camera per player, scissored to their own
+    // half of the window; everything else gets a single full-window camera.
+    for camera in world.cameras() {
+        canvas.set_scissor_rect(camera.viewport)?;
+        draw_scene_for_camera(world, ctx, canvas, &camera, shake_offset)?;
+    }
+    canvas.set_scissor_rect(Rect::new(0.0, 0.0, WINDOW_WIDTH, WINDOW_HEIGHT))?;
+
+    Ok(())
+}
+
+/// Draws the arena, trails, particles, cycles, HUD and (when toggled) the
+/// AI debug overlay for a single `camera`'s viewport. The caller is
+/// expected to have already scissored `canvas` to `camera.viewport`.
+fn draw_scene_for_camera(
+    world: &mut World,
+    ctx: &mut Context,
+    canvas: &mut graphics::Canvas,
+    camera: &Camera,
+    shake_offset: Point2<f32>,
+) -> GameResult {
+    let offset = Point2 {
+        x: camera.offset().x + shake_offset.x,
+        y: camera.offset().y + shake_offset.y,
+    };
+
+    // Draw grid background with 8-bit style grid lines
+    let mut mesh_builder = MeshBuilder::new();
+
+    // Draw border with glow effect
+    mesh_builder.rectangle(
+        DrawMode::stroke(3.0),
+        Rect::new(0.0, 0.0, GRID_WIDTH, GRID_HEIGHT),
+        Color::from_rgb(0, 100, 200),
+    )?;
+
+    // Draw vertical grid lines
+    let grid_spacing = 50.0;
+    let mut x = grid_spacing;
+    while x < GRID_WIDTH {
+        mesh_builder.line(
+            &[Point2 { x, y: 0.0 }, Point2 { x, y: GRID_HEIGHT }],
+            1.0,
+            Color::from_rgba(20, 40, 60, 50),
+        )?;
+        x += grid_spacing;
+    }
+
+    // Draw horizontal grid lines
+    let mut y = grid_spacing;
+    while y < GRID_HEIGHT {
+        mesh_builder.line(
+            &[Point2 { x: 0.0, y }, Point2 { x: GRID_WIDTH, y }],
+            1.0,
+            Color::from_rgba(20, 40, 60, 50),
+        )?;
+        y += grid_spacing;
+    }
+
+    let grid_mesh = graphics::Mesh::from_data(ctx, mesh_builder.build());
+    canvas.draw(&grid_mesh, DrawParam::default().dest(offset));
+
+    // Draw procedural arena walls
+    if !world.arena.segments.is_empty() {
+        let mut wall_mesh_builder = MeshBuilder::new();
+        for &(start, end) in &world.arena.segments {
+            wall_mesh_builder.line(&[start, end], CELL_SIZE, Color::from_rgb(150, 150, 160))?;
+        }
+        let wall_mesh = graphics::Mesh::from_data(ctx, wall_mesh_builder.build());
+        canvas.draw(&wall_mesh, DrawParam::default().dest(offset));
+    }
+
+    // Draw procedural arena blocks (e.g. `ArenaLayout::Caves`'s cellular-automata walls)
+    if !world.arena.blocks.is_empty() {
+        let mut block_mesh_builder = MeshBuilder::new();
+        for &block in &world.arena.blocks {
+            block_mesh_builder.rectangle(DrawMode::fill(), block, Color::from_rgb(150, 150, 160))?;
+        }
+        let block_mesh = graphics::Mesh::from_data(ctx, block_mesh_builder.build());
+        canvas.draw(&block_mesh, DrawParam::default().dest(offset));
+    }
+
+    // Draw trails with glow effect
+    for cycle in &world.cycles {
+        if cycle.trail.len() >= 2 {
+            let trail_vec: Vec<Point2<f32>> = cycle.trail.iter().copied().collect();
+            let mut mesh_builder = MeshBuilder::new();
+
+            // Draw outer glow layer
+            for i in 0..trail_vec.len() - 1 {
+                let glow_color = Color::new(
+                    cycle.color.r * 0.3,
+                    cycle.color.g * 0.3,
+                    cycle.color.b * 0.3,
+                    0.3,
                 );
+                mesh_builder.line(
+                    &[trail_vec[i], trail_vec[i + 1]],
+                    CELL_SIZE * 2.5,
+                    glow_color,
+                )?;
+            }
 
-                let controls_text = graphics::Text::new("P1: WASD + LShift (boost) | P2: Arrows + RShift (boost)");
-                canvas.draw(
-                    &controls_text,
-                    DrawParam::default()
-                        .dest([GRID_WIDTH / 2.0 - 230.0, 600.0])
-                        .color(Color::from_rgb(128, 128, 128)),
+            // Draw main trail
+            for i in 0..trail_vec.len() - 1 {
+                mesh_builder.line(
+                    &[trail_vec[i], trail_vec[i + 1]],
+                    CELL_SIZE,
+                    cycle.color,
+                )?;
+            }
+
+            // Draw bright core
+            for i in 0..trail_vec.len() - 1 {
+                let core_color = Color::new(
+                    (cycle.color.r * 1.2).min(1.0),
+                    (cycle.color.g * 1.2).min(1.0),
+                    (cycle.color.b * 1.2).min(1.0),
+                    1.0,
                 );
+                mesh_builder.line(
+                    &[trail_vec[i], trail_vec[i + 1]],
+                    CELL_SIZE * 0.5,
+                    core_color,
+                )?;
             }
-            GameMode::Playing => {
-                // Apply screen shake
-                let shake_offset = if self.screen_shake > 0.0 {
-                    let mut rng = rand::thread_rng();
-                    Point2 {
-                        x: rng.gen_range(-self.screen_shake..self.screen_shake),
-                        y: rng.gen_range(-self.screen_shake..self.screen_shake),
-                    }
-                } else {
-                    Point2 { x: 0.0, y: 0.0 }
-                };
-                
-                // Draw grid background with 8-bit style grid lines
-                let mut mesh_builder = MeshBuilder::new();
-                
-                // Draw border with glow effect
+
+            let mesh = graphics::Mesh::from_data(ctx, mesh_builder.build());
+            canvas.draw(&mesh, DrawParam::default().dest(offset));
+        }
+    }
+
+    // Draw particles (explosions, boost trail, ...) from whichever
+    // emitter preset spawned them.
+    for particle in world.particles.iter() {
+        let pos = Point2 {
+            x: particle.position.x + offset.x,
+            y: particle.position.y + offset.y,
+        };
+        let mut mesh_builder = MeshBuilder::new();
+        match particle.shape() {
+            ParticleShape::Circle => {
+                mesh_builder.circle(DrawMode::fill(), pos, particle.size(), 0.5, particle.color())?;
+            }
+            ParticleShape::Square => {
+                let size = particle.size();
                 mesh_builder.rectangle(
-                    DrawMode::stroke(3.0),
-                    Rect::new(0.0, 0.0, GRID_WIDTH, GRID_HEIGHT),
-                    Color::from_rgb(0, 100, 200),
+                    DrawMode::fill(),
+                    Rect::new(pos.x - size * 0.5, pos.y - size * 0.5, size, size),
+                    particle.color(),
                 )?;
-                
-                // Draw vertical grid lines
-                let grid_spacing = 50.0;
-                let mut x = grid_spacing;
-                while x < GRID_WIDTH {
-                    mesh_builder.line(
-                        &[Point2 { x, y: 0.0 }, Point2 { x, y: GRID_HEIGHT }],
-                        1.0,
-                        Color::from_rgba(20, 40, 60, 50),
-                    )?;
-                    x += grid_spacing;
-                }
-                
-                // Draw horizontal grid lines
-                let mut y = grid_spacing;
-                while y < GRID_HEIGHT {
-                    mesh_builder.line(
-                        &[Point2 { x: 0.0, y }, Point2 { x: GRID_WIDTH, y }],
-                        1.0,
-                        Color::from_rgba(20, 40, 60, 50),
-                    )?;
-                    y += grid_spacing;
-                }
-                
-                let grid_mesh = graphics::Mesh::from_data(ctx, mesh_builder.build());
-                canvas.draw(&grid_mesh, DrawParam::default().dest(shake_offset));
-
-                // Draw trails with glow effect
-                for cycle in &self.cycles {
-                    if cycle.trail.len() >= 2 {
-                        let trail_vec: Vec<Point2<f32>> = cycle.trail.iter().copied().collect();
-                        let mut mesh_builder = MeshBuilder::new();
-                        
-                        // Draw outer glow layer
-                        for i in 0..trail_vec.len() - 1 {
-                            let glow_color = Color::new(
-                                cycle.color.r * 0.3,
-                                cycle.color.g * 0.3,
-                                cycle.color.b * 0.3,
-                                0.3,
-                            );
-                            mesh_builder.line(
-                                &[trail_vec[i], trail_vec[i + 1]],
-                                CELL_SIZE * 2.5,
-                                glow_color,
-                            )?;
-                        }
-                        
-                        // Draw main trail
-                        for i in 0..trail_vec.len() - 1 {
-                            mesh_builder.line(
-                                &[trail_vec[i], trail_vec[i + 1]],
-                                CELL_SIZE,
-                                cycle.color,
-                            )?;
-                        }
-                        
-                        // Draw bright core
-                        for i in 0..trail_vec.len() - 1 {
-                            let core_color = Color::new(
-                                (cycle.color.r * 1.2).min(1.0),
-                                (cycle.color.g * 1.2).min(1.0),
-                                (cycle.color.b * 1.2).min(1.0),
-                                1.0,
-                            );
-                            mesh_builder.line(
-                                &[trail_vec[i], trail_vec[i + 1]],
-                                CELL_SIZE * 0.5,
-                                core_color,
-                            )?;
-                        }
-                        
-                        let mesh = graphics::Mesh::from_data(ctx, mesh_builder.build());
-                        canvas.draw(&mesh, DrawParam::default().dest(shake_offset));
-                    }
-                }
-                
-                // Draw trail particles
-                for particle in &self.trail_particles {
-                    let mesh = graphics::Mesh::from_data(
-                        ctx,
-                        MeshBuilder::new()
-                            .circle(
-                                DrawMode::fill(),
-                                Point2 {
-                                    x: particle.position.x + shake_offset.x,
-                                    y: particle.position.y + shake_offset.y,
-                                },
-                                3.0 * particle.lifetime * 2.0,
-                                0.5,
-                                particle.color,
-                            )?
-                            .build(),
-                    );
-                    canvas.draw(&mesh, DrawParam::default());
-                }
+            }
+        };
+        let mesh = graphics::Mesh::from_data(ctx, mesh_builder.build());
+        canvas.draw(&mesh, DrawParam::default());
+    }
 
-                // Draw cycles as 8-bit style vehicles
-                for cycle in &self.cycles {
-                    if cycle.alive {
-                        let mut mesh_builder = MeshBuilder::new();
-                        
-                        // Calculate cycle orientation
-                        let (body_width, body_height) = match cycle.direction {
-                            Direction::Up | Direction::Down => (CYCLE_WIDTH, CYCLE_HEIGHT),
-                            Direction::Left | Direction::Right => (CYCLE_HEIGHT, CYCLE_WIDTH),
-                        };
-                        
-                        // Draw boost effect if active
-                        if cycle.is_boosting {
-                            // Draw boost trail particles
-                            let boost_color = Color::new(
-                                1.0,
-                                0.8,
-                                0.2,
-                                0.5,
-                            );
-                            mesh_builder.circle(
-                                DrawMode::fill(),
-                                cycle.position,
-                                body_width * 2.5,
-                                0.1,
-                                boost_color,
-                            )?;
-                        }
-                        
-                        // Draw large glow effect
-                        let glow_intensity = if cycle.is_boosting { 0.6 } else { 0.4 };
-                        let glow_size = if cycle.is_boosting { 2.0 } else { 1.5 };
-                        let glow_color = Color::new(
-                            cycle.color.r * glow_intensity,
-                            cycle.color.g * glow_intensity,
-                            cycle.color.b * glow_intensity,
-                            0.2,
-                        );
-                        mesh_builder.circle(
-                            DrawMode::fill(),
-                            cycle.position,
-                            body_width * glow_size,
-                            0.1,
-                            glow_color,
-                        )?;
-                        
-                        // Draw main body (8-bit styled rectangle)
-                        mesh_builder.rectangle(
-                            DrawMode::fill(),
-                            Rect::new(
-                                cycle.position.x - body_width / 2.0,
-                                cycle.position.y - body_height / 2.0,
-                                body_width,
-                                body_height,
-                            ),
-                            cycle.color,
-                        )?;
-                        
-                        // Draw body outline for retro effect
-                        mesh_builder.rectangle(
-                            DrawMode::stroke(2.0),
-                            Rect::new(
-                                cycle.position.x - body_width / 2.0,
-                                cycle.position.y - body_height / 2.0,
-                                body_width,
-                                body_height,
-                            ),
-                            Color::new(
-                                (cycle.color.r * 1.3).min(1.0),
-                                (cycle.color.g * 1.3).min(1.0),
-                                (cycle.color.b * 1.3).min(1.0),
-                                1.0,
-                            ),
-                        )?;
-                        
-                        // Draw cockpit/core as bright pixel
-                        mesh_builder.rectangle(
-                            DrawMode::fill(),
-                            Rect::new(
-                                cycle.position.x - 4.0,
-                                cycle.position.y - 4.0,
-                                8.0,
-                                8.0,
-                            ),
-                            Color::WHITE,
-                        )?;
-                        
-                        // Draw directional lights (8-bit style pixels)
-                        let (light1_x, light1_y, light2_x, light2_y) = match cycle.direction {
-                            Direction::Up => (
-                                cycle.position.x - 6.0, cycle.position.y - body_height / 2.0 + 4.0,
-                                cycle.position.x + 6.0, cycle.position.y - body_height / 2.0 + 4.0,
-                            ),
-                            Direction::Down => (
-                                cycle.position.x - 6.0, cycle.position.y + body_height / 2.0 - 4.0,
-                                cycle.position.x + 6.0, cycle.position.y + body_height / 2.0 - 4.0,
-                            ),
-                            Direction::Left => (
-                                cycle.position.x - body_width / 2.0 + 4.0, cycle.position.y - 6.0,
-                                cycle.position.x - body_width / 2.0 + 4.0, cycle.position.y + 6.0,
-                            ),
-                            Direction::Right => (
-                                cycle.position.x + body_width / 2.0 - 4.0, cycle.position.y - 6.0,
-                                cycle.position.x + body_width / 2.0 - 4.0, cycle.position.y + 6.0,
-                            ),
-                        };
-                        
-                        // Draw headlights as bright pixels
-                        mesh_builder.rectangle(
-                            DrawMode::fill(),
-                            Rect::new(light1_x - 2.0, light1_y - 2.0, 4.0, 4.0),
-                            Color::from_rgb(255, 255, 200),
-                        )?;
-                        mesh_builder.rectangle(
-                            DrawMode::fill(),
-                            Rect::new(light2_x - 2.0, light2_y - 2.0, 4.0, 4.0),
-                            Color::from_rgb(255, 255, 200),
-                        )?;
-                        
-                        let mesh = graphics::Mesh::from_data(ctx, mesh_builder.build());
-                        canvas.draw(&mesh, DrawParam::default().dest(shake_offset));
-                    }
-                }
+    // Draw cycles as 8-bit style vehicles
+    for cycle in &world.cycles {
+        if cycle.alive {
+            let mut mesh_builder = MeshBuilder::new();
 
-                // Draw explosions
-                for explosion in &self.explosions {
-                    for particle in &explosion.particles {
-                        let alpha = (particle.lifetime / 1.5).min(1.0);
-                        let color = Color::new(
-                            particle.color.r,
-                            particle.color.g,
-                            particle.color.b,
-                            particle.color.a * alpha,
-                        );
-                        
-                        let mesh = graphics::Mesh::from_data(
-                            ctx,
-                            MeshBuilder::new()
-                                .rectangle(
-                                    DrawMode::fill(),
-                                    Rect::new(
-                                        particle.position.x - 2.0,
-                                        particle.position.y - 2.0,
-                                        4.0,
-                                        4.0,
-                                    ),
-                                    color,
-                                )?
-                                .build(),
-                        );
-                        canvas.draw(&mesh, DrawParam::default().dest(shake_offset));
-                    }
-                }
-                
-                // Draw HUD
-                let hud_text = "Press P to Pause | Press ESC to Quit";
-                let hud = graphics::Text::new(hud_text);
-                canvas.draw(
-                    &hud,
-                    DrawParam::default()
-                        .dest([10.0, 10.0])
-                        .color(Color::from_rgba(200, 200, 200, 180)),
+            // Calculate cycle orientation
+            let (body_width, body_height) = match cycle.direction {
+                Direction::Up | Direction::Down => (CYCLE_WIDTH, CYCLE_HEIGHT),
+                Direction::Left | Direction::Right => (CYCLE_HEIGHT, CYCLE_WIDTH),
+            };
+
+            // Draw boost effect if active
+            if cycle.is_boosting {
+                // Draw boost trail particles
+                let boost_color = Color::new(
+                    1.0,
+                    0.8,
+                    0.2,
+                    0.5,
                 );
-                
-                // Draw boost energy bars
-                for (i, cycle) in self.cycles.iter().enumerate() {
-                    if cycle.alive && cycle.player_type == PlayerType::Human {
-                        let bar_x = if i == 0 { 10.0 } else { GRID_WIDTH - 210.0 };
-                        let bar_y = 40.0;
-                        
-                        // Draw background bar
-                        let bg_mesh = graphics::Mesh::from_data(
-                            ctx,
-                            MeshBuilder::new()
-                                .rectangle(
-                                    DrawMode::stroke(2.0),
-                                    Rect::new(bar_x, bar_y, 200.0, 20.0),
-                                    Color::from_rgb(50, 50, 50),
-                                )?
-                                .build(),
-                        );
-                        canvas.draw(&bg_mesh, DrawParam::default());
-                        
-                        // Draw energy fill
-                        let energy_width = (cycle.boost_energy / MAX_BOOST_ENERGY) * 196.0;
-                        let energy_color = if cycle.is_boosting {
-                            Color::from_rgb(255, 255, 100)
-                        } else if cycle.boost_energy > 50.0 {
-                            Color::from_rgb(0, 255, 100)
-                        } else if cycle.boost_energy > 20.0 {
-                            Color::from_rgb(255, 200, 0)
-                        } else {
-                            Color::from_rgb(255, 50, 50)
-                        };
-                        
-                        if energy_width > 0.0 {
-                            let energy_mesh = graphics::Mesh::from_data(
-                                ctx,
-                                MeshBuilder::new()
-                                    .rectangle(
-                                        DrawMode::fill(),
-                                        Rect::new(bar_x + 2.0, bar_y + 2.0, energy_width, 16.0),
-                                        energy_color,
-                                    )?
-                                    .build(),
-                            );
-                            canvas.draw(&energy_mesh, DrawParam::default());
-                        }
-                        
-                        // Draw label
-                        let label = if i == 0 { "P1 Boost" } else { "P2 Boost" };
-                        let label_text = graphics::Text::new(label);
-                        canvas.draw(
-                            &label_text,
-                            DrawParam::default()
-                                .dest([bar_x, bar_y - 15.0])
-                                .color(cycle.color)
-                                .scale([0.8, 0.8]),
-                        );
-                    }
-                }
-            }
-            GameMode::Paused => {
-                // No shake in pause mode
-                let shake_offset = Point2 { x: 0.0, y: 0.0 };
-                
-                // Draw the game state in background (dimmed)
-                // First draw the game normally
-                let mut mesh_builder = MeshBuilder::new();
-                
-                // Draw border
-                mesh_builder.rectangle(
-                    DrawMode::stroke(3.0),
-                    Rect::new(0.0, 0.0, GRID_WIDTH, GRID_HEIGHT),
-                    Color::from_rgb(0, 50, 100),
+                mesh_builder.circle(
+                    DrawMode::fill(),
+                    cycle.position,
+                    body_width * 2.5,
+                    0.1,
+                    boost_color,
                 )?;
-                
-                let grid_mesh = graphics::Mesh::from_data(ctx, mesh_builder.build());
-                canvas.draw(&grid_mesh, DrawParam::default().dest(shake_offset));
-                
-                // Draw trails (dimmed)
-                for cycle in &self.cycles {
-                    if cycle.trail.len() >= 2 {
-                        let trail_vec: Vec<Point2<f32>> = cycle.trail.iter().copied().collect();
-                        let mut mesh_builder = MeshBuilder::new();
-                        
-                        for i in 0..trail_vec.len() - 1 {
-                            let dimmed_color = Color::new(
-                                cycle.color.r * 0.3,
-                                cycle.color.g * 0.3,
-                                cycle.color.b * 0.3,
-                                0.5,
-                            );
-                            mesh_builder.line(
-                                &[trail_vec[i], trail_vec[i + 1]],
-                                CELL_SIZE,
-                                dimmed_color,
-                            )?;
-                        }
-                        
-                        let mesh = graphics::Mesh::from_data(ctx, mesh_builder.build());
-                        canvas.draw(&mesh, DrawParam::default());
-                    }
-                }
-                
-                // Draw pause overlay
-                let overlay = graphics::Mesh::from_data(
+            }
+
+            // Draw large glow effect
+            let glow_intensity = if cycle.is_boosting { 0.6 } else { 0.4 };
+            let glow_size = if cycle.is_boosting { 2.0 } else { 1.5 };
+            let glow_color = Color::new(
+                cycle.color.r * glow_intensity,
+                cycle.color.g * glow_intensity,
+                cycle.color.b * glow_intensity,
+                0.2,
+            );
+            mesh_builder.circle(
+                DrawMode::fill(),
+                cycle.position,
+                body_width * glow_size,
+                0.1,
+                glow_color,
+            )?;
+
+            // Draw main body (8-bit styled rectangle)
+            mesh_builder.rectangle(
+                DrawMode::fill(),
+                Rect::new(
+                    cycle.position.x - body_width / 2.0,
+                    cycle.position.y - body_height / 2.0,
+                    body_width,
+                    body_height,
+                ),
+                cycle.color,
+            )?;
+
+            // Draw body outline for retro effect
+            mesh_builder.rectangle(
+                DrawMode::stroke(2.0),
+                Rect::new(
+                    cycle.position.x - body_width / 2.0,
+                    cycle.position.y - body_height / 2.0,
+                    body_width,
+                    body_height,
+                ),
+                Color::new(
+                    (cycle.color.r * 1.3).min(1.0),
+                    (cycle.color.g * 1.3).min(1.0),
+                    (cycle.color.b * 1.3).min(1.0),
+                    1.0,
+                ),
+            )?;
+
+            // Draw cockpit/core as bright pixel
+            mesh_builder.rectangle(
+                DrawMode::fill(),
+                Rect::new(
+                    cycle.position.x - 4.0,
+                    cycle.position.y - 4.0,
+                    8.0,
+                    8.0,
+                ),
+                Color::WHITE,
+            )?;
+
+            // Draw directional lights (8-bit style pixels)
+            let (light1_x, light1_y, light2_x, light2_y) = match cycle.direction {
+                Direction::Up => (
+                    cycle.position.x - 6.0, cycle.position.y - body_height / 2.0 + 4.0,
+                    cycle.position.x + 6.0, cycle.position.y - body_height / 2.0 + 4.0,
+                ),
+                Direction::Down => (
+                    cycle.position.x - 6.0, cycle.position.y + body_height / 2.0 - 4.0,
+                    cycle.position.x + 6.0, cycle.position.y + body_height / 2.0 - 4.0,
+                ),
+                Direction::Left => (
+                    cycle.position.x - body_width / 2.0 + 4.0, cycle.position.y - 6.0,
+                    cycle.position.x - body_width / 2.0 + 4.0, cycle.position.y + 6.0,
+                ),
+                Direction::Right => (
+                    cycle.position.x + body_width / 2.0 - 4.0, cycle.position.y - 6.0,
+                    cycle.position.x + body_width / 2.0 - 4.0, cycle.position.y + 6.0,
+                ),
+            };
+
+            // Draw headlights as bright pixels
+            mesh_builder.rectangle(
+                DrawMode::fill(),
+                Rect::new(light1_x - 2.0, light1_y - 2.0, 4.0, 4.0),
+                Color::from_rgb(255, 255, 200),
+            )?;
+            mesh_builder.rectangle(
+                DrawMode::fill(),
+                Rect::new(light2_x - 2.0, light2_y - 2.0, 4.0, 4.0),
+                Color::from_rgb(255, 255, 200),
+            )?;
+
+            let mesh = graphics::Mesh::from_data(ctx, mesh_builder.build());
+            canvas.draw(&mesh, DrawParam::default().dest(offset));
+        }
+    }
+
+    // Draw HUD
+    draw_text(
+        world,
+        ctx,
+        canvas,
+        "Press P to Pause | Press ESC to Quit | Press F3 for AI Debug Overlay",
+        [camera.viewport.x + 10.0, camera.viewport.y + 10.0],
+        1.0,
+        Color::from_rgba(200, 200, 200, 180),
+    );
+
+    // Draw this camera's own boost energy bar, if it's following a human.
+    if let Some(i) = camera.follows {
+        let cycle = &world.cycles[i];
+        if cycle.alive && cycle.player_type == PlayerType::Human {
+            let bar_x = camera.viewport.x + 10.0;
+            let bar_y = camera.viewport.y + 40.0;
+
+            // Draw background bar
+            let bg_mesh = graphics::Mesh::from_data(
+                ctx,
+                MeshBuilder::new()
+                    .rectangle(
+                        DrawMode::stroke(2.0),
+                        Rect::new(bar_x, bar_y, 200.0, 20.0),
+                        Color::from_rgb(50, 50, 50),
+                    )?
+                    .build(),
+            );
+            canvas.draw(&bg_mesh, DrawParam::default());
+
+            // Draw energy fill
+            let energy_width = (cycle.boost_energy / MAX_BOOST_ENERGY) * 196.0;
+            let energy_color = if cycle.is_boosting {
+                Color::from_rgb(255, 255, 100)
+            } else if cycle.boost_energy > 50.0 {
+                Color::from_rgb(0, 255, 100)
+            } else if cycle.boost_energy > 20.0 {
+                Color::from_rgb(255, 200, 0)
+            } else {
+                Color::from_rgb(255, 50, 50)
+            };
+
+            if energy_width > 0.0 {
+                let energy_mesh = graphics::Mesh::from_data(
                     ctx,
                     MeshBuilder::new()
                         .rectangle(
                             DrawMode::fill(),
-                            Rect::new(0.0, 0.0, GRID_WIDTH, GRID_HEIGHT),
-                            Color::from_rgba(0, 0, 0, 180),
+                            Rect::new(bar_x + 2.0, bar_y + 2.0, energy_width, 16.0),
+                            energy_color,
                         )?
                         .build(),
                 );
-                canvas.draw(&overlay, DrawParam::default());
-                
-                // Draw pause text
-                let pause_text = graphics::Text::new("PAUSED");
-                canvas.draw(
-                    &pause_text,
-                    DrawParam::default()
-                        .dest([GRID_WIDTH / 2.0 - 100.0, 350.0])
-                        .color(Color::from_rgb(255, 255, 255))
-                        .scale([3.0, 3.0]),
-                );
-                
-                let resume_text = graphics::Text::new("Press P to Resume");
-                canvas.draw(
-                    &resume_text,
-                    DrawParam::default()
-                        .dest([GRID_WIDTH / 2.0 - 80.0, 450.0])
-                        .color(Color::from_rgb(200, 200, 200)),
-                );
-                
-                let quit_text = graphics::Text::new("Press ESC to Return to Menu");
-                canvas.draw(
-                    &quit_text,
-                    DrawParam::default()
-                        .dest([GRID_WIDTH / 2.0 - 120.0, 500.0])
-                        .color(Color::from_rgb(200, 200, 200)),
-                );
+                canvas.draw(&energy_mesh, DrawParam::default());
             }
-            GameMode::GameOver { winner } => {
-                let game_over_text = graphics::Text::new("GAME OVER");
-                canvas.draw(
-                    &game_over_text,
-                    DrawParam::default()
-                        .dest([GRID_WIDTH / 2.0 - 200.0, 350.0])
-                        .color(Color::from_rgb(255, 0, 0))
-                        .scale([3.0, 3.0]),
-                );
 
-                let winner_text = graphics::Text::new(winner.clone());
-                canvas.draw(
-                    &winner_text,
-                    DrawParam::default()
-                        .dest([GRID_WIDTH / 2.0 - 100.0, 450.0])
-                        .color(Color::from_rgb(0, 255, 0))
-                        .scale([1.5, 1.5]),
-                );
+            // Draw label
+            let label = if i == 0 { "P1 Boost" } else { "P2 Boost" };
+            draw_text(world, ctx, canvas, label, [bar_x, bar_y - 15.0], 0.8, cycle.color);
+        }
+    }
+
+    // AI debug overlay (F3): ai_update's decision data for each
+    // computer cycle, recorded into `debug_info` instead of
+    // discarded so it can be visualized here.
+    if world.debug_overlay {
+        for cycle in &world.cycles {
+            if cycle.player_type != PlayerType::Computer || !cycle.alive {
+                continue;
+            }
+            let info = &cycle.debug_info;
+            let mut overlay_mesh_builder = MeshBuilder::new();
+
+            // Forward look-ahead probe: green if clear, red if it triggered a turn.
+            let probe_color = if info.should_turn {
+                Color::from_rgb(255, 80, 80)
+            } else {
+                Color::from_rgb(80, 255, 80)
+            };
+            overlay_mesh_builder.line(&[cycle.position, info.look_ahead_point], 1.0, probe_color)?;
+            overlay_mesh_builder.circle(DrawMode::stroke(1.5), info.look_ahead_point, 6.0, 0.5, probe_color)?;
+
+            // The specific trail/wall point that triggered should_turn, if any.
+            if let Some(trigger) = info.triggering_point {
+                overlay_mesh_builder.circle(DrawMode::fill(), trigger, 5.0, 0.5, Color::from_rgb(255, 0, 0))?;
+            }
+
+            // Candidate directions/headings judged safe this frame.
+            for &dir in &info.safe_dirs {
+                let velocity = dir.to_velocity();
+                let end = Point2 {
+                    x: cycle.position.x + velocity.0 * 30.0,
+                    y: cycle.position.y + velocity.1 * 30.0,
+                };
+                overlay_mesh_builder.line(&[cycle.position, end], 1.5, Color::from_rgb(100, 200, 255))?;
+            }
+            for &heading in &info.safe_headings {
+                let end = Point2 {
+                    x: cycle.position.x + heading.cos() * 30.0,
+                    y: cycle.position.y + heading.sin() * 30.0,
+                };
+                overlay_mesh_builder.line(&[cycle.position, end], 1.5, Color::from_rgb(100, 200, 255))?;
+            }
 
-                let restart_text = graphics::Text::new("Press ESC to return to menu");
-                canvas.draw(
-                    &restart_text,
-                    DrawParam::default()
-                        .dest([GRID_WIDTH / 2.0 - 120.0, 550.0])
-                        .color(Color::WHITE),
+            let overlay_mesh = graphics::Mesh::from_data(ctx, overlay_mesh_builder.build());
+            canvas.draw(&overlay_mesh, DrawParam::default().dest(offset));
+
+            // Hard AI's open-space score for each candidate, parallel to
+            // whichever of safe_dirs/safe_headings is populated.
+            let candidate_count = info.safe_dirs.len().max(info.safe_headings.len());
+            for i in 0..candidate_count {
+                let Some(&score) = info.open_space_scores.get(i) else {
+                    continue;
+                };
+                let end = if let Some(&dir) = info.safe_dirs.get(i) {
+                    let velocity = dir.to_velocity();
+                    Point2 {
+                        x: cycle.position.x + velocity.0 * 30.0,
+                        y: cycle.position.y + velocity.1 * 30.0,
+                    }
+                } else {
+                    let heading = info.safe_headings[i];
+                    Point2 {
+                        x: cycle.position.x + heading.cos() * 30.0,
+                        y: cycle.position.y + heading.sin() * 30.0,
+                    }
+                };
+                draw_text(
+                    world,
+                    ctx,
+                    canvas,
+                    &format!("{:.0}", score),
+                    [end.x + offset.x, end.y + offset.y],
+                    0.7,
+                    Color::from_rgb(100, 200, 255),
                 );
             }
+
+            draw_text(
+                world,
+                ctx,
+                canvas,
+                &format!("{:?}", cycle.ai_difficulty),
+                [cycle.position.x - 20.0 + offset.x, cycle.position.y - 25.0 + offset.y],
+                0.7,
+                Color::from_rgb(200, 200, 255),
+            );
         }
+    }
+
+    Ok(())
+}
+
+struct MenuState;
+
+impl AppState for MenuState {
+    fn draw(&self, world: &mut World, ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult {
+        draw_text(
+            world,
+            ctx,
+            canvas,
+            "LIGHT CYCLE",
+            [WINDOW_WIDTH / 2.0 - 200.0, 300.0],
+            4.0,
+            Color::from_rgb(0, 255, 255),
+        );
+
+        draw_text(
+            world,
+            ctx,
+            canvas,
+            "Press 1 for Single Player",
+            [WINDOW_WIDTH / 2.0 - 120.0, 420.0],
+            1.0,
+            Color::WHITE,
+        );
+
+        draw_text(
+            world,
+            ctx,
+            canvas,
+            "Press 2 for Two Players",
+            [WINDOW_WIDTH / 2.0 - 120.0, 460.0],
+            1.0,
+            Color::WHITE,
+        );
+
+        draw_text(
+            world,
+            ctx,
+            canvas,
+            &format!("AI Difficulty: {:?} (Press D to change)", world.ai_difficulty),
+            [WINDOW_WIDTH / 2.0 - 160.0, 520.0],
+            1.0,
+            match world.ai_difficulty {
+                AIDifficulty::Easy => Color::from_rgb(100, 255, 100),
+                AIDifficulty::Medium => Color::from_rgb(255, 255, 100),
+                AIDifficulty::Hard => Color::from_rgb(255, 100, 100),
+            },
+        );
+
+        draw_text(
+            world,
+            ctx,
+            canvas,
+            &format!("Steering: {:?} (Press F to change)", world.steering_mode),
+            [WINDOW_WIDTH / 2.0 - 160.0, 550.0],
+            1.0,
+            Color::from_rgb(100, 200, 255),
+        );
+
+        draw_text(
+            world,
+            ctx,
+            canvas,
+            &format!("Arena: {:?} (Press L to change)", world.arena_layout),
+            [WINDOW_WIDTH / 2.0 - 160.0, 580.0],
+            1.0,
+            Color::from_rgb(200, 150, 255),
+        );
+
+        draw_text(
+            world,
+            ctx,
+            canvas,
+            "P1: WASD + LShift (boost) | P2: Arrows + RShift (boost)",
+            [WINDOW_WIDTH / 2.0 - 230.0, 620.0],
+            1.0,
+            Color::from_rgb(128, 128, 128),
+        );
+
+        draw_text(
+            world,
+            ctx,
+            canvas,
+            "Press R to Watch Last Match Replay",
+            [WINDOW_WIDTH / 2.0 - 180.0, 660.0],
+            1.0,
+            Color::from_rgb(128, 128, 128),
+        );
 
-        canvas.finish(ctx)?;
         Ok(())
     }
 
-    fn key_down_event(&mut self, _ctx: &mut Context, input: KeyInput, _repeat: bool) -> GameResult {
-        if let Some(keycode) = input.keycode {
-            match self.mode {
-                GameMode::Menu => {
-                    match keycode {
-                        KeyCode::Key1 => self.start_game(true),
-                        KeyCode::Key2 => self.start_game(false),
-                        KeyCode::D => {
-                            self.ai_difficulty = match self.ai_difficulty {
-                                AIDifficulty::Easy => AIDifficulty::Medium,
-                                AIDifficulty::Medium => AIDifficulty::Hard,
-                                AIDifficulty::Hard => AIDifficulty::Easy,
-                            };
-                        }
-                        _ => {}
-                    }
+    fn handle_key(&mut self, world: &mut World, keycode: KeyCode, pressed: bool) -> Transition {
+        if !pressed {
+            return Transition::None;
+        }
+        match keycode {
+            KeyCode::Key1 => {
+                world.start_game(true);
+                Transition::Push(Box::new(PlayingState))
+            }
+            KeyCode::Key2 => {
+                world.start_game(false);
+                Transition::Push(Box::new(PlayingState))
+            }
+            KeyCode::D => {
+                world.ai_difficulty = match world.ai_difficulty {
+                    AIDifficulty::Easy => AIDifficulty::Medium,
+                    AIDifficulty::Medium => AIDifficulty::Hard,
+                    AIDifficulty::Hard => AIDifficulty::Easy,
+                };
+                Transition::None
+            }
+            KeyCode::R => {
+                if let Some(replay) = replay::Replay::load(LAST_MATCH_REPLAY_PATH) {
+                    world.start_replay(replay);
+                    Transition::Push(Box::new(ReplayState))
+                } else {
+                    Transition::None
                 }
-                GameMode::Playing => {
-                    match keycode {
-                        KeyCode::P => {
-                            self.mode = GameMode::Paused;
-                        }
-                        KeyCode::Escape => {
-                            self.mode = GameMode::Menu;
-                        }
-                        _ => {
-                            for cycle in &mut self.cycles {
-                                cycle.handle_input(keycode, true);
-                            }
-                        }
-                    }
+            }
+            KeyCode::F => {
+                world.steering_mode = match world.steering_mode {
+                    SteeringMode::Grid => SteeringMode::FreeAngle,
+                    SteeringMode::FreeAngle => SteeringMode::Grid,
+                };
+                Transition::None
+            }
+            KeyCode::L => {
+                world.arena_layout = world.arena_layout.next();
+                Transition::None
+            }
+            _ => Transition::None,
+        }
+    }
+}
+
+struct PlayingState;
+
+impl AppState for PlayingState {
+    fn update(&mut self, world: &mut World, ctx: &mut Context, dt: f32) -> Transition {
+        world.step(dt);
+        if let Err(e) = world.mix_audio(ctx) {
+            eprintln!("audio mix failed: {e}");
+        }
+        match world.finish_if_game_over() {
+            Some(winner) => Transition::Replace(Box::new(GameOverState { winner })),
+            None => Transition::None,
+        }
+    }
+
+    fn draw(&self, world: &mut World, ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult {
+        draw_match_scene(world, ctx, canvas)
+    }
+
+    fn handle_key(&mut self, world: &mut World, keycode: KeyCode, pressed: bool) -> Transition {
+        if pressed {
+            match keycode {
+                KeyCode::P => return Transition::Push(Box::new(PauseOverlayState)),
+                KeyCode::Escape => return Transition::Replace(Box::new(MenuState)),
+                KeyCode::F3 => {
+                    world.debug_overlay = !world.debug_overlay;
                 }
-                GameMode::Paused => {
-                    match keycode {
-                        KeyCode::P => {
-                            self.mode = GameMode::Playing;
-                        }
-                        KeyCode::Escape => {
-                            self.mode = GameMode::Menu;
+                _ => {
+                    world.recording.record(world.frame_count, keycode, true);
+                    for cycle in &mut world.cycles {
+                        let was_boosting = cycle.is_boosting;
+                        cycle.handle_input(keycode, true);
+                        if !was_boosting && cycle.is_boosting {
+                            world.audio.trigger_boost_start();
                         }
-                        _ => {}
                     }
                 }
-                GameMode::GameOver { .. } => {
-                    if keycode == KeyCode::Escape {
-                        self.mode = GameMode::Menu;
-                    }
+            }
+        } else {
+            world.recording.record(world.frame_count, keycode, false);
+            for cycle in &mut world.cycles {
+                cycle.handle_input(keycode, false);
+            }
+        }
+        Transition::None
+    }
+}
+
+/// Watches a recorded match: drives the same `draw_match_scene` as
+/// `PlayingState`, but steps via `World::step_replay` instead of reacting to
+/// real key events, and can't be paused.
+struct ReplayState;
+
+impl AppState for ReplayState {
+    fn update(&mut self, world: &mut World, ctx: &mut Context, dt: f32) -> Transition {
+        world.step_replay(dt);
+        if let Err(e) = world.mix_audio(ctx) {
+            eprintln!("audio mix failed: {e}");
+        }
+        match world.finish_if_game_over() {
+            Some(winner) => Transition::Replace(Box::new(GameOverState { winner })),
+            None => Transition::None,
+        }
+    }
+
+    fn draw(&self, world: &mut World, ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult {
+        draw_match_scene(world, ctx, canvas)
+    }
+
+    fn handle_key(&mut self, _world: &mut World, keycode: KeyCode, pressed: bool) -> Transition {
+        if pressed && keycode == KeyCode::Escape {
+            Transition::Replace(Box::new(MenuState))
+        } else {
+            Transition::None
+        }
+    }
+}
+
+/// Pushed on top of `PlayingState` by the `P` key. Freezes the match (its
+/// `update` is never called while this is on top of the stack) and paints a
+/// translucent overlay + pause text over whatever `PlayingState` already
+/// drew underneath, instead of redrawing the scene itself.
+struct PauseOverlayState;
+
+impl AppState for PauseOverlayState {
+    fn draw(&self, world: &mut World, ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult {
+        let overlay = graphics::Mesh::from_data(
+            ctx,
+            MeshBuilder::new()
+                .rectangle(
+                    DrawMode::fill(),
+                    Rect::new(0.0, 0.0, WINDOW_WIDTH, WINDOW_HEIGHT),
+                    Color::from_rgba(0, 0, 0, 180),
+                )?
+                .build(),
+        );
+        canvas.draw(&overlay, DrawParam::default());
+
+        draw_text(
+            world,
+            ctx,
+            canvas,
+            "PAUSED",
+            [WINDOW_WIDTH / 2.0 - 100.0, 350.0],
+            3.0,
+            Color::from_rgb(255, 255, 255),
+        );
+
+        draw_text(
+            world,
+            ctx,
+            canvas,
+            "Press P to Resume",
+            [WINDOW_WIDTH / 2.0 - 80.0, 450.0],
+            1.0,
+            Color::from_rgb(200, 200, 200),
+        );
+
+        draw_text(
+            world,
+            ctx,
+            canvas,
+            "Press ESC to Return to Menu",
+            [WINDOW_WIDTH / 2.0 - 120.0, 500.0],
+            1.0,
+            Color::from_rgb(200, 200, 200),
+        );
+
+        Ok(())
+    }
+
+    fn handle_key(&mut self, _world: &mut World, keycode: KeyCode, pressed: bool) -> Transition {
+        if !pressed {
+            return Transition::None;
+        }
+        match keycode {
+            KeyCode::P => Transition::Pop,
+            KeyCode::Escape => Transition::Replace(Box::new(MenuState)),
+            _ => Transition::None,
+        }
+    }
+}
+
+struct GameOverState {
+    winner: String,
+}
+
+impl AppState for GameOverState {
+    fn draw(&self, world: &mut World, ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult {
+        draw_text(
+            world,
+            ctx,
+            canvas,
+            "GAME OVER",
+            [WINDOW_WIDTH / 2.0 - 200.0, 350.0],
+            3.0,
+            Color::from_rgb(255, 0, 0),
+        );
+
+        draw_text(
+            world,
+            ctx,
+            canvas,
+            &self.winner,
+            [WINDOW_WIDTH / 2.0 - 100.0, 450.0],
+            1.5,
+            Color::from_rgb(0, 255, 0),
+        );
+
+        draw_text(
+            world,
+            ctx,
+            canvas,
+            "Press ESC to return to menu",
+            [WINDOW_WIDTH / 2.0 - 120.0, 550.0],
+            1.0,
+            Color::WHITE,
+        );
+
+        Ok(())
+    }
+
+    fn handle_key(&mut self, _world: &mut World, keycode: KeyCode, pressed: bool) -> Transition {
+        if pressed && keycode == KeyCode::Escape {
+            Transition::Replace(Box::new(MenuState))
+        } else {
+            Transition::None
+        }
+    }
+}
+
+/// Top-level `ggez::EventHandler`: owns the shared `World` and a stack of
+/// `AppState`s. Only the top of the stack receives `update`/`handle_key`,
+/// but `draw` walks the whole stack bottom-to-top so overlay states (e.g.
+/// `PauseOverlayState`) can paint on top of whatever is beneath them.
+struct GameState {
+    world: World,
+    stack: Vec<Box<dyn AppState>>,
+}
+
+impl GameState {
+    fn new(ctx: &mut Context) -> Self {
+        GameState {
+            world: World::new(ctx),
+            stack: vec![Box::new(MenuState)],
+        }
+    }
+
+    fn apply(&mut self, transition: Transition) {
+        match transition {
+            Transition::None => {}
+            Transition::Push(mut state) => {
+                state.enter(&mut self.world);
+                self.stack.push(state);
+            }
+            Transition::Pop => {
+                if let Some(mut state) = self.stack.pop() {
+                    state.leave(&mut self.world);
+                }
+            }
+            Transition::Replace(mut state) => {
+                while let Some(mut old) = self.stack.pop() {
+                    old.leave(&mut self.world);
                 }
+                state.enter(&mut self.world);
+                self.stack.push(state);
             }
         }
+    }
+}
+
+impl EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        let dt = 1.0 / 60.0;
+        let transition = match self.stack.last_mut() {
+            Some(top) => top.update(&mut self.world, ctx, dt),
+            None => Transition::None,
+        };
+        self.apply(transition);
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let mut canvas = graphics::Canvas::from_frame(ctx, Color::BLACK);
+
+        let world = &mut self.world;
+        for state in &self.stack {
+            state.draw(world, ctx, &mut canvas)?;
+        }
+
+        canvas.finish(ctx)?;
+        Ok(())
+    }
+
+    fn key_down_event(&mut self, _ctx: &mut Context, input: KeyInput, _repeat: bool) -> GameResult {
+        if let Some(keycode) = input.keycode {
+            let transition = match self.stack.last_mut() {
+                Some(top) => top.handle_key(&mut self.world, keycode, true),
+                None => Transition::None,
+            };
+            self.apply(transition);
+        }
         Ok(())
     }
-    
+
     fn key_up_event(&mut self, _ctx: &mut Context, input: KeyInput) -> GameResult {
         if let Some(keycode) = input.keycode {
-            if let GameMode::Playing = self.mode {
-                for cycle in &mut self.cycles {
-                    cycle.handle_input(keycode, false);
-                }
-            }
+            let transition = match self.stack.last_mut() {
+                Some(top) => top.handle_key(&mut self.world, keycode, false),
+                None => Transition::None,
+            };
+            self.apply(transition);
         }
         Ok(())
     }
 }
 
 fn main() -> GameResult {
+    if std::env::args().any(|arg| arg == "--train") {
+        trainer::train_and_save(HARD_AI_GENOME_PATH);
+        return Ok(());
+    }
+
     let cb = ContextBuilder::new("lightcycle", "TRON")
         .window_mode(ggez::conf::WindowMode::default()
-            .dimensions(GRID_WIDTH, GRID_HEIGHT)
+            .dimensions(WINDOW_WIDTH, WINDOW_HEIGHT)
             .resizable(false));
-    let (ctx, event_loop) = cb.build()?;
-    let state = GameState::new();
+    let (mut ctx, event_loop) = cb.build()?;
+    let state = GameState::new(&mut ctx);
     event::run(ctx, event_loop, state)
 }
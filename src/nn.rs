@@ -0,0 +1,121 @@
+//! Minimal feedforward network used as the "Hard" AI policy.
+//!
+//! The whole network is a flat `Vec<f32>` genome so the genetic trainer in
+//! `trainer.rs` can crossover/mutate it without knowing anything about
+//! layers or activations.
+
+pub const SENSOR_COUNT: usize = 6; // 5 raycasts + normalized boost_energy
+pub const HIDDEN_COUNT: usize = 8;
+pub const OUTPUT_COUNT: usize = 4; // straight, turn left, turn right, boost
+
+/// A single hidden-layer network: `tanh` hidden activations, raw linear
+/// outputs (the caller decides straight/left/right via argmax and boost via
+/// a 0.5 threshold).
+#[derive(Clone, Debug)]
+pub struct FeedForwardNet {
+    input_size: usize,
+    hidden_size: usize,
+    output_size: usize,
+    genome: Vec<f32>,
+}
+
+impl FeedForwardNet {
+    /// Number of genes a network with this shape needs.
+    pub fn genome_len(input_size: usize, hidden_size: usize, output_size: usize) -> usize {
+        (input_size * hidden_size + hidden_size) + (hidden_size * output_size + output_size)
+    }
+
+    /// Build a network from a flat genome produced by the trainer. Panics if
+    /// the genome is the wrong length for the requested shape.
+    pub fn from_genome(input_size: usize, hidden_size: usize, output_size: usize, genome: Vec<f32>) -> Self {
+        assert_eq!(
+            genome.len(),
+            Self::genome_len(input_size, hidden_size, output_size),
+            "genome length does not match network shape"
+        );
+        FeedForwardNet {
+            input_size,
+            hidden_size,
+            output_size,
+            genome,
+        }
+    }
+
+    pub fn genome(&self) -> &[f32] {
+        &self.genome
+    }
+
+    fn hidden_weights(&self) -> &[f32] {
+        &self.genome[0..self.input_size * self.hidden_size]
+    }
+
+    fn hidden_biases(&self) -> &[f32] {
+        let start = self.input_size * self.hidden_size;
+        &self.genome[start..start + self.hidden_size]
+    }
+
+    fn output_weights(&self) -> &[f32] {
+        let start = self.input_size * self.hidden_size + self.hidden_size;
+        &self.genome[start..start + self.hidden_size * self.output_size]
+    }
+
+    fn output_biases(&self) -> &[f32] {
+        let start = self.input_size * self.hidden_size + self.hidden_size + self.hidden_size * self.output_size;
+        &self.genome[start..start + self.output_size]
+    }
+
+    /// Run the network forward. `inputs.len()` must equal `self.input_size`.
+    pub fn forward(&self, inputs: &[f32]) -> Vec<f32> {
+        debug_assert_eq!(inputs.len(), self.input_size);
+
+        let hidden_w = self.hidden_weights();
+        let hidden_b = self.hidden_biases();
+        let mut hidden = vec![0.0f32; self.hidden_size];
+        for h in 0..self.hidden_size {
+            let mut sum = hidden_b[h];
+            for i in 0..self.input_size {
+                sum += inputs[i] * hidden_w[i * self.hidden_size + h];
+            }
+            hidden[h] = sum.tanh();
+        }
+
+        let out_w = self.output_weights();
+        let out_b = self.output_biases();
+        let mut outputs = vec![0.0f32; self.output_size];
+        for o in 0..self.output_size {
+            let mut sum = out_b[o];
+            for h in 0..self.hidden_size {
+                sum += hidden[h] * out_w[h * self.output_size + o];
+            }
+            outputs[o] = sum;
+        }
+        outputs
+    }
+}
+
+/// Decision derived from the raw network outputs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Decision {
+    Straight,
+    TurnLeft,
+    TurnRight,
+}
+
+/// Interpret the first three outputs as {straight, left, right} via argmax
+/// and the (optional) fourth as a boost flag thresholded at 0.5.
+pub fn decide(outputs: &[f32]) -> (Decision, bool) {
+    let (mut best_idx, mut best_val) = (0usize, outputs[0]);
+    for (i, &v) in outputs.iter().take(3).enumerate() {
+        if v > best_val {
+            best_idx = i;
+            best_val = v;
+        }
+    }
+    let decision = match best_idx {
+        0 => Decision::Straight,
+        1 => Decision::TurnLeft,
+        _ => Decision::TurnRight,
+    };
+    let boost = outputs.get(3).copied().unwrap_or(0.0) > 0.5;
+    (decision, boost)
+}
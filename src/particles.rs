@@ -0,0 +1,311 @@
+//! Data-driven particle/effect system.
+//!
+//! Emitters (explosion bursts, the boost trail, ...) used to be hard-coded
+//! structs with inline speed/lifetime/color constants. Instead, each named
+//! preset is described by an `EmitterConfig`/`ParticleConfig` pair loaded
+//! from a RON file at startup, so effects can be retuned or new ones added
+//! without recompiling. `ParticleSystem` owns every live particle and is
+//! generic over whatever presets the loaded `EmitterLibrary` defines.
+
+use crate::GameRng;
+use ggez::graphics::Color;
+use ggez::mint::Point2;
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+
+/// Default location for the emitter preset file, relative to the working
+/// directory (matching `HARD_AI_GENOME_PATH`/`LAST_MATCH_REPLAY_PATH` in
+/// `main.rs`).
+pub const EMITTER_CONFIG_PATH: &str = "particles.ron";
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub enum ParticleShape {
+    Circle,
+    Square,
+}
+
+/// Per-particle physical and visual tuning for one emitter preset. The RGB
+/// of a spawned particle comes from the caller's `tint` (a cycle's color,
+/// say); this only controls the alpha fade, size-over-life, and motion.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ParticleConfig {
+    pub shape: ParticleShape,
+    pub lifetime_range: (f32, f32),
+    pub speed_range: (f32, f32),
+    /// Half-angle, in degrees, that a spawned particle's direction may
+    /// stray from the emitter's heading. 180 gives a full circle.
+    pub angle_jitter_deg: f32,
+    pub size_start: f32,
+    pub size_end: f32,
+    pub alpha_start_range: (f32, f32),
+    pub alpha_end: f32,
+    /// Multiplied into velocity every tick; < 1.0 slows particles down.
+    pub drag: f32,
+    pub gravity: (f32, f32),
+}
+
+/// How many particles one `emit` call spawns, and what they look like.
+#[derive(Clone, Deserialize)]
+pub struct EmitterConfig {
+    pub particle_count: u32,
+    /// Spawn rate for `ParticleSystem::emit_continuous`, e.g. a boost trail
+    /// streaming while the key is held rather than bursting all at once.
+    /// Ignored by one-shot `emit` bursts.
+    pub particles_per_second: f32,
+    pub particle: ParticleConfig,
+}
+
+#[derive(Deserialize)]
+struct EmitterLibraryFile {
+    emitters: HashMap<String, EmitterConfig>,
+}
+
+/// The set of named emitter presets available to `ParticleSystem::emit`.
+pub struct EmitterLibrary {
+    emitters: HashMap<String, EmitterConfig>,
+}
+
+impl EmitterLibrary {
+    /// Load presets from `path`, falling back to the built-in defaults
+    /// (tuned to match the original hard-coded explosion/boost-trail
+    /// behavior) if the file is missing or fails to parse.
+    pub fn load(path: &str) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => match ron::from_str::<EmitterLibraryFile>(&contents) {
+                Ok(file) => EmitterLibrary { emitters: file.emitters },
+                Err(e) => {
+                    eprintln!("failed to parse {path}: {e}, using built-in emitter presets");
+                    Self::defaults()
+                }
+            },
+            Err(_) => Self::defaults(),
+        }
+    }
+
+    fn defaults() -> Self {
+        let mut emitters = HashMap::new();
+        emitters.insert(
+            "explosion".to_string(),
+            EmitterConfig {
+                particle_count: 50,
+                particles_per_second: 0.0,
+                particle: ParticleConfig {
+                    shape: ParticleShape::Square,
+                    lifetime_range: (0.5, 1.5),
+                    speed_range: (50.0, 200.0),
+                    angle_jitter_deg: 180.0,
+                    size_start: 4.0,
+                    size_end: 4.0,
+                    alpha_start_range: (0.5, 1.0),
+                    alpha_end: 0.0,
+                    drag: 0.98,
+                    gravity: (0.0, 0.0),
+                },
+            },
+        );
+        emitters.insert(
+            "boost_trail".to_string(),
+            EmitterConfig {
+                particle_count: 1,
+                // Matches the original hard-coded "30% chance per 60Hz frame".
+                particles_per_second: 18.0,
+                particle: ParticleConfig {
+                    shape: ParticleShape::Circle,
+                    lifetime_range: (0.2, 0.5),
+                    speed_range: (0.0, 15.0),
+                    angle_jitter_deg: 45.0,
+                    size_start: 6.0,
+                    size_end: 0.0,
+                    alpha_start_range: (0.3, 0.7),
+                    alpha_end: 0.0,
+                    drag: 0.95,
+                    gravity: (0.0, 0.0),
+                },
+            },
+        );
+        emitters.insert(
+            "wall_spark".to_string(),
+            EmitterConfig {
+                particle_count: 12,
+                particles_per_second: 0.0,
+                particle: ParticleConfig {
+                    shape: ParticleShape::Square,
+                    lifetime_range: (0.2, 0.6),
+                    speed_range: (30.0, 120.0),
+                    angle_jitter_deg: 60.0,
+                    size_start: 3.0,
+                    size_end: 1.0,
+                    alpha_start_range: (0.6, 1.0),
+                    alpha_end: 0.0,
+                    drag: 0.9,
+                    gravity: (0.0, 40.0),
+                },
+            },
+        );
+        emitters.insert(
+            "spawn_burst".to_string(),
+            EmitterConfig {
+                particle_count: 24,
+                particles_per_second: 0.0,
+                particle: ParticleConfig {
+                    shape: ParticleShape::Circle,
+                    lifetime_range: (0.4, 0.9),
+                    speed_range: (20.0, 100.0),
+                    angle_jitter_deg: 180.0,
+                    size_start: 5.0,
+                    size_end: 0.0,
+                    alpha_start_range: (0.6, 1.0),
+                    alpha_end: 0.0,
+                    drag: 0.96,
+                    gravity: (0.0, 0.0),
+                },
+            },
+        );
+        EmitterLibrary { emitters }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&EmitterConfig> {
+        self.emitters.get(name)
+    }
+}
+
+/// One live particle spawned from an `EmitterConfig`.
+pub struct Particle {
+    pub position: Point2<f32>,
+    velocity: Point2<f32>,
+    lifetime: f32,
+    max_lifetime: f32,
+    tint: Color,
+    config: ParticleConfig,
+}
+
+impl Particle {
+    fn update(&mut self, dt: f32) {
+        self.velocity.x = self.velocity.x * self.config.drag + self.config.gravity.0 * dt;
+        self.velocity.y = self.velocity.y * self.config.drag + self.config.gravity.1 * dt;
+        self.position.x += self.velocity.x * dt;
+        self.position.y += self.velocity.y * dt;
+        self.lifetime -= dt;
+    }
+
+    fn age(&self) -> f32 {
+        (1.0 - self.lifetime / self.max_lifetime).clamp(0.0, 1.0)
+    }
+
+    pub fn shape(&self) -> ParticleShape {
+        self.config.shape
+    }
+
+    pub fn size(&self) -> f32 {
+        let t = self.age();
+        self.config.size_start + (self.config.size_end - self.config.size_start) * t
+    }
+
+    /// The particle's current color, gradient-faded from its sampled start
+    /// alpha down to `alpha_end` over its lifetime.
+    pub fn color(&self) -> Color {
+        let t = self.age();
+        let alpha_start = self.tint.a;
+        let alpha = alpha_start + (self.config.alpha_end - alpha_start) * t;
+        Color::new(self.tint.r, self.tint.g, self.tint.b, alpha)
+    }
+}
+
+/// Owns every particle spawned from any emitter preset.
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+}
+
+impl ParticleSystem {
+    pub fn new() -> Self {
+        ParticleSystem { particles: Vec::new() }
+    }
+
+    /// Spawn `library`'s `name` preset at `position`, biased toward
+    /// `heading` (radians), tinted with `tint`'s RGB.
+    pub fn emit(
+        &mut self,
+        library: &EmitterLibrary,
+        name: &str,
+        position: Point2<f32>,
+        heading: f32,
+        tint: Color,
+        rng: &mut GameRng,
+    ) {
+        let Some(emitter) = library.get(name) else {
+            return;
+        };
+        for _ in 0..emitter.particle_count {
+            self.spawn_particle(&emitter.particle, position, heading, tint, rng);
+        }
+    }
+
+    /// Like `emit`, but for an emitter meant to stream continuously (e.g. a
+    /// boost trail while the key is held) rather than burst all at once.
+    /// Spawns at `particles_per_second`, carrying the fractional remainder
+    /// in `accumulator` across calls so the rate is correct regardless of
+    /// `dt`.
+    pub fn emit_continuous(
+        &mut self,
+        library: &EmitterLibrary,
+        name: &str,
+        position: Point2<f32>,
+        heading: f32,
+        tint: Color,
+        dt: f32,
+        rng: &mut GameRng,
+        accumulator: &mut f32,
+    ) {
+        let Some(emitter) = library.get(name) else {
+            return;
+        };
+        *accumulator += emitter.particles_per_second * dt;
+        while *accumulator >= 1.0 {
+            *accumulator -= 1.0;
+            self.spawn_particle(&emitter.particle, position, heading, tint, rng);
+        }
+    }
+
+    fn spawn_particle(
+        &mut self,
+        config: &ParticleConfig,
+        position: Point2<f32>,
+        heading: f32,
+        tint: Color,
+        rng: &mut GameRng,
+    ) {
+        let jitter = config.angle_jitter_deg.to_radians();
+        let angle = (heading + rng.gen_range(-jitter..=jitter)) % TAU;
+        let speed = rng.gen_range(config.speed_range.0..=config.speed_range.1);
+        let lifetime = rng.gen_range(config.lifetime_range.0..=config.lifetime_range.1);
+        let alpha = rng.gen_range(config.alpha_start_range.0..=config.alpha_start_range.1);
+        self.particles.push(Particle {
+            position,
+            velocity: Point2 {
+                x: angle.cos() * speed,
+                y: angle.sin() * speed,
+            },
+            lifetime,
+            max_lifetime: lifetime,
+            tint: Color::new(tint.r, tint.g, tint.b, alpha),
+            config: config.clone(),
+        });
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for particle in &mut self.particles {
+            particle.update(dt);
+        }
+        self.particles.retain(|p| p.lifetime > 0.0);
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Particle> {
+        self.particles.iter()
+    }
+}
@@ -0,0 +1,197 @@
+//! Record/playback of a match: the RNG seed, the menu selections in effect
+//! when it started, plus every human input event tagged with the frame it
+//! occurred on, so a `ReplayState` can recreate the same match setup and
+//! feed the same events back in at the same frames to reproduce it exactly.
+
+use crate::arena::ArenaLayout;
+use crate::{AIDifficulty, SteeringMode};
+use ggez::input::keyboard::KeyCode;
+use std::fs;
+use std::io::Write;
+
+/// A single human key event, tagged with the frame it was received on.
+#[derive(Clone, Copy, Debug)]
+pub struct RecordedEvent {
+    pub frame: u64,
+    pub keycode: KeyCode,
+    pub pressed: bool,
+}
+
+/// A fully recorded match: the seed the `GameRng` was created with, the
+/// menu selections `start_game_with_seed` used to set it up, and every
+/// human input event in frame order. All of these are needed to actually
+/// reproduce the recorded match rather than whatever the menu happens to
+/// be set to when the replay is watched.
+#[derive(Clone, Debug)]
+pub struct Replay {
+    pub seed: u64,
+    pub single_player: bool,
+    pub ai_difficulty: AIDifficulty,
+    pub steering_mode: SteeringMode,
+    pub arena_layout: ArenaLayout,
+    pub events: Vec<RecordedEvent>,
+}
+
+/// Only the keys this game actually reads need a text encoding; anything
+/// else is dropped rather than recorded.
+fn keycode_name(keycode: KeyCode) -> Option<&'static str> {
+    Some(match keycode {
+        KeyCode::W => "W",
+        KeyCode::A => "A",
+        KeyCode::S => "S",
+        KeyCode::D => "D",
+        KeyCode::Up => "Up",
+        KeyCode::Down => "Down",
+        KeyCode::Left => "Left",
+        KeyCode::Right => "Right",
+        KeyCode::LShift => "LShift",
+        KeyCode::RShift => "RShift",
+        _ => return None,
+    })
+}
+
+fn keycode_from_name(name: &str) -> Option<KeyCode> {
+    Some(match name {
+        "W" => KeyCode::W,
+        "A" => KeyCode::A,
+        "S" => KeyCode::S,
+        "D" => KeyCode::D,
+        "Up" => KeyCode::Up,
+        "Down" => KeyCode::Down,
+        "Left" => KeyCode::Left,
+        "Right" => KeyCode::Right,
+        "LShift" => KeyCode::LShift,
+        "RShift" => KeyCode::RShift,
+        _ => return None,
+    })
+}
+
+fn ai_difficulty_name(difficulty: AIDifficulty) -> &'static str {
+    match difficulty {
+        AIDifficulty::Easy => "Easy",
+        AIDifficulty::Medium => "Medium",
+        AIDifficulty::Hard => "Hard",
+    }
+}
+
+fn ai_difficulty_from_name(name: &str) -> Option<AIDifficulty> {
+    Some(match name {
+        "Easy" => AIDifficulty::Easy,
+        "Medium" => AIDifficulty::Medium,
+        "Hard" => AIDifficulty::Hard,
+        _ => return None,
+    })
+}
+
+fn steering_mode_name(mode: SteeringMode) -> &'static str {
+    match mode {
+        SteeringMode::Grid => "Grid",
+        SteeringMode::FreeAngle => "FreeAngle",
+    }
+}
+
+fn steering_mode_from_name(name: &str) -> Option<SteeringMode> {
+    Some(match name {
+        "Grid" => SteeringMode::Grid,
+        "FreeAngle" => SteeringMode::FreeAngle,
+        _ => return None,
+    })
+}
+
+fn arena_layout_name(layout: ArenaLayout) -> &'static str {
+    match layout {
+        ArenaLayout::Empty => "Empty",
+        ArenaLayout::SymmetricPillars => "SymmetricPillars",
+        ArenaLayout::Maze => "Maze",
+        ArenaLayout::Caves => "Caves",
+    }
+}
+
+fn arena_layout_from_name(name: &str) -> Option<ArenaLayout> {
+    Some(match name {
+        "Empty" => ArenaLayout::Empty,
+        "SymmetricPillars" => ArenaLayout::SymmetricPillars,
+        "Maze" => ArenaLayout::Maze,
+        "Caves" => ArenaLayout::Caves,
+        _ => return None,
+    })
+}
+
+impl Replay {
+    pub fn new(
+        seed: u64,
+        single_player: bool,
+        ai_difficulty: AIDifficulty,
+        steering_mode: SteeringMode,
+        arena_layout: ArenaLayout,
+    ) -> Self {
+        Replay {
+            seed,
+            single_player,
+            ai_difficulty,
+            steering_mode,
+            arena_layout,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, frame: u64, keycode: KeyCode, pressed: bool) {
+        if keycode_name(keycode).is_some() {
+            self.events.push(RecordedEvent {
+                frame,
+                keycode,
+                pressed,
+            });
+        }
+    }
+
+    /// Save as a plain text file: the seed and match setup on the first
+    /// five lines, then one `frame keycode pressed` line per recorded
+    /// event.
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        let mut file = fs::File::create(path)?;
+        writeln!(file, "{}", self.seed)?;
+        writeln!(file, "{}", self.single_player)?;
+        writeln!(file, "{}", ai_difficulty_name(self.ai_difficulty))?;
+        writeln!(file, "{}", steering_mode_name(self.steering_mode))?;
+        writeln!(file, "{}", arena_layout_name(self.arena_layout))?;
+        for event in &self.events {
+            if let Some(name) = keycode_name(event.keycode) {
+                writeln!(file, "{} {} {}", event.frame, name, event.pressed)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn load(path: &str) -> Option<Replay> {
+        let contents = fs::read_to_string(path).ok()?;
+        let mut lines = contents.lines();
+        let seed: u64 = lines.next()?.trim().parse().ok()?;
+        let single_player: bool = lines.next()?.trim().parse().ok()?;
+        let ai_difficulty = ai_difficulty_from_name(lines.next()?.trim())?;
+        let steering_mode = steering_mode_from_name(lines.next()?.trim())?;
+        let arena_layout = arena_layout_from_name(lines.next()?.trim())?;
+
+        let mut events = Vec::new();
+        for line in lines {
+            let mut parts = line.split_whitespace();
+            let frame: u64 = parts.next()?.parse().ok()?;
+            let keycode = keycode_from_name(parts.next()?)?;
+            let pressed: bool = parts.next()?.parse().ok()?;
+            events.push(RecordedEvent {
+                frame,
+                keycode,
+                pressed,
+            });
+        }
+
+        Some(Replay {
+            seed,
+            single_player,
+            ai_difficulty,
+            steering_mode,
+            arena_layout,
+            events,
+        })
+    }
+}
@@ -0,0 +1,172 @@
+//! Headless genetic-algorithm trainer for the NN "Hard" AI.
+//!
+//! Runs with no ggez window: matches are simulated at a fixed `dt` against
+//! a heuristic `Medium` sparring partner, and fitness is frames survived
+//! plus trail length. Run with `cargo run -- --train`.
+
+use crate::collision::CollisionGrid;
+use crate::nn::{self, FeedForwardNet};
+use crate::{AIDifficulty, Direction, GameRng, LightCycle, PlayerType, SteeringMode, GRID_HEIGHT, GRID_WIDTH};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fs;
+use std::io::Write;
+
+const POPULATION_SIZE: usize = 100;
+const GENERATIONS: usize = 200;
+const MAX_FRAMES: usize = 60 * 60; // 60 seconds per match
+const ELITE_FRACTION: f32 = 0.2;
+const MUTATION_RATE: f32 = 0.05;
+const MUTATION_SIGMA: f32 = 0.3;
+const TRAIN_SEED: u64 = 0xC0FFEE;
+
+type Genome = Vec<f32>;
+
+fn genome_len() -> usize {
+    FeedForwardNet::genome_len(nn::SENSOR_COUNT, nn::HIDDEN_COUNT, nn::OUTPUT_COUNT)
+}
+
+fn random_genome(rng: &mut StdRng) -> Genome {
+    (0..genome_len()).map(|_| rng.gen_range(-1.0..1.0)).collect()
+}
+
+/// Play one genome-controlled cycle against a heuristic `Medium` opponent
+/// for up to `MAX_FRAMES` fixed-`dt` steps. Returns a fitness score.
+fn simulate_match(genome: &Genome, seed: u64) -> f32 {
+    let dt = 1.0 / 60.0;
+    let net = FeedForwardNet::from_genome(nn::SENSOR_COUNT, nn::HIDDEN_COUNT, nn::OUTPUT_COUNT, genome.clone());
+
+    let mut trainee = LightCycle::new(
+        200.0,
+        GRID_HEIGHT / 2.0,
+        Direction::Right,
+        ggez::graphics::Color::WHITE,
+        PlayerType::Computer,
+        None,
+        None,
+        AIDifficulty::Hard,
+        Some(net),
+        SteeringMode::Grid,
+    );
+    let mut sparring_partner = LightCycle::new(
+        GRID_WIDTH - 200.0,
+        GRID_HEIGHT / 2.0,
+        Direction::Left,
+        ggez::graphics::Color::WHITE,
+        PlayerType::Computer,
+        None,
+        None,
+        AIDifficulty::Medium,
+        None,
+        SteeringMode::Grid,
+    );
+
+    let mut rng = GameRng::seeded(seed);
+    let mut grid = CollisionGrid::new(2);
+    let mut frames_survived = 0usize;
+
+    for _ in 0..MAX_FRAMES {
+        trainee.ai_update(&grid, 0, &mut rng);
+        sparring_partner.ai_update(&grid, 1, &mut rng);
+
+        trainee.update(dt, &mut grid, 0, 2);
+        sparring_partner.update(dt, &mut grid, 1, 2);
+
+        if !trainee.alive {
+            break;
+        }
+        frames_survived += 1;
+    }
+
+    frames_survived as f32 + trainee.trail.len() as f32
+}
+
+fn crossover(rng: &mut StdRng, a: &Genome, b: &Genome) -> Genome {
+    a.iter()
+        .zip(b.iter())
+        .map(|(&ga, &gb)| if rng.gen_bool(0.5) { ga } else { gb })
+        .collect()
+}
+
+fn mutate(rng: &mut StdRng, genome: &mut Genome) {
+    for gene in genome.iter_mut() {
+        if rng.gen_bool(MUTATION_RATE as f64) {
+            *gene += rng.gen_range(-MUTATION_SIGMA..MUTATION_SIGMA);
+        }
+    }
+}
+
+/// Run the genetic algorithm for `GENERATIONS` generations and return the
+/// best genome found. Uses a seeded `StdRng` so training is reproducible.
+pub fn train() -> Genome {
+    let mut rng = StdRng::seed_from_u64(TRAIN_SEED);
+    let mut population: Vec<Genome> = (0..POPULATION_SIZE).map(|_| random_genome(&mut rng)).collect();
+    let elite_count = ((POPULATION_SIZE as f32) * ELITE_FRACTION).max(1.0) as usize;
+
+    let mut best_genome = population[0].clone();
+    let mut best_fitness = f32::MIN;
+
+    for generation in 0..GENERATIONS {
+        let match_seed = TRAIN_SEED.wrapping_add(generation as u64);
+        let mut scored: Vec<(f32, Genome)> = population
+            .into_iter()
+            .map(|genome| {
+                let fitness = simulate_match(&genome, match_seed);
+                (fitness, genome)
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+
+        if scored[0].0 > best_fitness {
+            best_fitness = scored[0].0;
+            best_genome = scored[0].1.clone();
+        }
+
+        // Double-buffer: next generation is built from this one's elites.
+        let elites: Vec<Genome> = scored.iter().take(elite_count).map(|(_, g)| g.clone()).collect();
+        let mut next_generation = elites.clone();
+        while next_generation.len() < POPULATION_SIZE {
+            let parent_a = &elites[rng.gen_range(0..elites.len())];
+            let parent_b = &elites[rng.gen_range(0..elites.len())];
+            let mut child = crossover(&mut rng, parent_a, parent_b);
+            mutate(&mut rng, &mut child);
+            next_generation.push(child);
+        }
+        population = next_generation;
+    }
+
+    best_genome
+}
+
+/// Train and persist the best genome to `path` as newline-separated floats.
+pub fn train_and_save(path: &str) {
+    let best = train();
+    if let Err(e) = save_genome(path, &best) {
+        eprintln!("failed to save trained genome to {path}: {e}");
+    } else {
+        println!("saved trained genome to {path}");
+    }
+}
+
+fn save_genome(path: &str, genome: &Genome) -> std::io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    for gene in genome {
+        writeln!(file, "{gene}")?;
+    }
+    Ok(())
+}
+
+/// Load a previously trained genome from disk, if present and well formed.
+pub fn load_genome(path: &str) -> Option<Genome> {
+    let contents = fs::read_to_string(path).ok()?;
+    let genome: Genome = contents
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.trim().parse().ok())
+        .collect::<Option<Vec<f32>>>()?;
+    if genome.len() == genome_len() {
+        Some(genome)
+    } else {
+        None
+    }
+}